@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::components::map::{BuildingKind, Terrain};
+use crate::components::snapshot::{PlayerSnapshot, Snapshot};
+use crate::entity::player::PlayerIndex;
+use crate::entity::unit::UnitType;
+
+/// Each tile is drawn with two characters
+type Tile = [char; 2];
+type Occupant = (Option<PlayerIndex>, UnitType);
+
+const LEGEND: &str = "Legend: .. plain  ^^ forest  ~~ water  ## wall  ++ high ground  @@ capture point  \
+nB barrack  n$ bank  nC classic  nA archer  nK knight  nM mage  n* several units  ** several players  \
+(n = player: 0-9, then a-z and A-Z, - for neutral units)";
+/// Drawn for the owners, in player order
+const OWNER_SYMBOLS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Draw the map as a character grid, the same snapshot always gives the same text
+pub struct AsciiRenderer;
+
+impl AsciiRenderer {
+    pub fn render(snapshot: &Snapshot) -> String {
+        let width = *snapshot.map.get_width() as usize;
+        let mut out = String::new();
+        let border = format!("+{}+", "-".repeat(width * 2));
+        // Writing into a String never fails
+        let _ = writeln!(out, "=== Turn {} ===", snapshot.turn);
+        let _ = writeln!(out, "{}", border);
+        for line in Self::grid(snapshot) {
+            let _ = writeln!(out, "|{}|", line);
+        }
+        let _ = writeln!(out, "{}", border);
+        let _ = writeln!(out, "{}", LEGEND);
+        for (i, player) in snapshot.players.iter().enumerate() {
+            let _ = writeln!(out, "{}", Self::status_line(i, player));
+        }
+        out
+    }
+
+    pub fn legend() -> &'static str {
+        LEGEND
+    }
+
+    pub fn status_line(index: PlayerIndex, player: &PlayerSnapshot) -> String {
+        format!(
+            "P{} {} | money: {} | units: {} | score: {}",
+            Self::owner_symbol(&Some(index)),
+            player.name,
+            player.money,
+            player.units,
+            player.score
+        )
+    }
+
+    /// Rows of the map, without borders
+    pub fn grid(snapshot: &Snapshot) -> Vec<String> {
+        let map = &snapshot.map;
+        let (width, height) = (*map.get_width() as usize, *map.get_height() as usize);
+        let mut grid: Vec<Tile> = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let terrain = map.get_terrain(x as u32, y as u32);
+                grid.push(Self::terrain_tile(terrain.unwrap_or(&Terrain::Plain)));
+            }
+        }
+
+        for building in map.get_buildings() {
+            if let Some((x, y)) = map.tile_of(&building.coordinate) {
+                grid[y as usize * width + x as usize] = [
+                    Self::owner_symbol(&Some(building.owner)),
+                    Self::building_symbol(&building.kind),
+                ];
+            }
+        }
+
+        for capture_point in map.get_capture_points() {
+            if let Some((x, y)) = map.tile_of(&capture_point.coordinate) {
+                grid[y as usize * width + x as usize] = ['@', '@'];
+            }
+        }
+
+        let mut occupants: BTreeMap<(u32, u32), Vec<Occupant>> = BTreeMap::new();
+        for placed in &snapshot.units {
+            if let Some(tile) = map.tile_of(&placed.coordinate) {
+                occupants
+                    .entry(tile)
+                    .or_default()
+                    .push((*placed.unit.get_owner(), *placed.unit.get_unit_type()));
+            }
+        }
+        for ((x, y), units) in occupants.iter() {
+            let (owner, unit_type) = &units[0];
+            let same_owner = units.iter().all(|(other, _)| other == owner);
+            grid[*y as usize * width + *x as usize] = match (units.len(), same_owner) {
+                (1, _) => [Self::owner_symbol(owner), Self::unit_symbol(unit_type)],
+                (_, true) => [Self::owner_symbol(owner), '*'],
+                (_, false) => ['*', '*'],
+            };
+        }
+
+        grid.chunks(width.max(1))
+            .map(|row| row.iter().flat_map(|tile| tile.iter()).collect())
+            .collect()
+    }
+
+    fn terrain_tile(terrain: &Terrain) -> Tile {
+        match terrain {
+            Terrain::Plain => ['.', '.'],
+            Terrain::Forest => ['^', '^'],
+            Terrain::Water => ['~', '~'],
+            Terrain::Wall => ['#', '#'],
+            Terrain::HighGround => ['+', '+'],
+        }
+    }
+
+    fn building_symbol(kind: &BuildingKind) -> char {
+        match kind {
+            BuildingKind::Barrack => 'B',
+            BuildingKind::Bank => '$',
+        }
+    }
+
+    fn unit_symbol(unit_type: &UnitType) -> char {
+        match unit_type {
+            UnitType::Classic => 'C',
+            UnitType::Archer => 'A',
+            UnitType::Knight => 'K',
+            UnitType::Mage => 'M',
+        }
+    }
+
+    /// Character standing for the owner of a unit on the map
+    pub fn owner_symbol(owner: &Option<PlayerIndex>) -> char {
+        match owner {
+            // No game has that many players, the map would be unreadable anyway
+            Some(index) => OWNER_SYMBOLS.chars().nth(*index).unwrap_or('?'),
+            None => '-',
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_ascii_renderer {
+
+    use crate::components::map::{BuildingKind, BuildingSite, CapturePoint, Map, Terrain};
+    use crate::components::play_ground::Coordinate;
+    use crate::components::snapshot::{PlayerSnapshot, Snapshot, UnitSnapshot};
+    use crate::entity::unit::Unit;
+
+    use super::AsciiRenderer;
+
+    fn unit(identifier: i128, owner: usize, coordinate: (f32, f32)) -> UnitSnapshot {
+        let mut unit = Unit::from(identifier, 20, 10, 5, 15, Vec::new());
+        unit.set_owner(owner);
+        UnitSnapshot {
+            unit,
+            coordinate: coordinate.into(),
+        }
+    }
+
+    #[test]
+    pub fn should_draw_grid() {
+        let mut map = Map::new(4, 2).with_buildings(vec![BuildingSite {
+            kind: BuildingKind::Barrack,
+            owner: 1,
+            coordinate: Coordinate::from_tiles(3, 1),
+        }]);
+        map.set_terrain(1, 0, Terrain::Wall);
+        map.set_terrain(2, 1, Terrain::Water);
+        let snapshot = Snapshot {
+            turn: 2,
+            map,
+            units: vec![
+                unit(1, 0, (0.2, 0.4)),
+                unit(2, 1, (0.0, 1.2)),
+                unit(3, 1, (-0.4, 0.9)),
+            ],
+            players: vec![PlayerSnapshot {
+                name: "Tigran".to_string(),
+                money: 80,
+                units: 1,
+                score: 0,
+            }],
+            attacks: Vec::new(),
+        };
+
+        let expected = "=== Turn 2 ===\n\
+            +--------+\n\
+            |0C##....|\n\
+            |1*..~~1B|\n\
+            +--------+\n"
+            .to_string()
+            + super::LEGEND
+            + "\nP0 Tigran | money: 80 | units: 1 | score: 0\n";
+        assert_eq!(expected, AsciiRenderer::render(&snapshot));
+    }
+
+    #[test]
+    pub fn should_draw_capture_points_and_neutral_units() {
+        let map = Map::new(3, 1).with_capture_points(vec![
+            CapturePoint {
+                coordinate: Coordinate::from_tiles(1, 0),
+                radius: 1,
+                income: 0,
+                score: 1,
+            },
+            CapturePoint {
+                coordinate: Coordinate::from_tiles(2, 0),
+                radius: 1,
+                income: 0,
+                score: 1,
+            },
+        ]);
+        let snapshot = Snapshot {
+            turn: 1,
+            map,
+            units: vec![UnitSnapshot {
+                unit: Unit::from(1, 20, 10, 5, 15, Vec::new()),
+                coordinate: Coordinate::from_tiles(2, 0),
+            }],
+            players: Vec::new(),
+            attacks: Vec::new(),
+        };
+        assert_eq!(vec!["..@@-C"], AsciiRenderer::grid(&snapshot));
+    }
+
+    #[test]
+    pub fn should_draw_owners_past_nine_with_letters() {
+        let snapshot = Snapshot {
+            turn: 1,
+            map: Map::new(3, 1),
+            units: vec![
+                unit(1, 9, (0.0, 0.0)),
+                unit(2, 10, (1.0, 0.0)),
+                unit(3, 36, (2.0, 0.0)),
+            ],
+            players: Vec::new(),
+            attacks: Vec::new(),
+        };
+        assert_eq!(vec!["9CaCAC"], AsciiRenderer::grid(&snapshot));
+
+        let player = PlayerSnapshot {
+            name: "Emma".to_string(),
+            money: 0,
+            units: 1,
+            score: 0,
+        };
+        assert!(AsciiRenderer::status_line(10, &player).starts_with("Pa Emma"));
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::BTreeSet;
+
+use crate::components::tech_tree::Upgrades;
+use crate::components::unit_catalogue::UnitCatalogue;
 use crate::components::unit_factory::UnitFactory;
 use crate::entity::player::Player;
 use crate::entity::player::TurnStrategyRequester;
@@ -7,6 +11,8 @@ use crate::exceptions::RtsException;
 /// Produce units and take money from players
 pub struct Barrack {
     unit_factory: UnitFactory,
+    /// Unit types a player has to research before buying them
+    locked_units: BTreeSet<UnitType>,
 }
 
 /// Produce money and give it to players
@@ -14,17 +20,18 @@ pub struct Bank;
 
 impl Default for Barrack {
     fn default() -> Self {
-        Self::new()
+        Self::with_catalogue(UnitCatalogue::default())
     }
 }
 
-const NEW_MONEY_BATCH: i32 = 100;
-
 impl Bank {
+    /// Give the amount, plus the income researched by the player
     pub fn give_money<T: TurnStrategyRequester>(
         player: &mut Player<T>,
+        amount: i32,
     ) -> Result<(), RtsException> {
-        if let Some(_money) = player.update_money(NEW_MONEY_BATCH) {
+        let income = player.get_research().get_upgrades().income;
+        if let Some(_money) = player.update_money(amount.saturating_add(income)) {
             Ok(())
         } else {
             Err(RtsException::UpdatePlayerException(
@@ -35,19 +42,43 @@ impl Bank {
 }
 
 impl Barrack {
-    fn new() -> Self {
+    /// Sell the units of the catalogue, at its prices
+    pub fn with_catalogue(catalogue: UnitCatalogue) -> Self {
         Barrack {
-            unit_factory: UnitFactory::default(),
+            unit_factory: UnitFactory::with_catalogue(catalogue),
+            locked_units: BTreeSet::new(),
         }
     }
 
+    /// Refuse to sell these unit types until the player unlocked them
+    pub fn with_locked_units(mut self, locked_units: BTreeSet<UnitType>) -> Self {
+        self.locked_units = locked_units;
+        self
+    }
+
+    pub fn get_catalogue(&self) -> &UnitCatalogue {
+        self.unit_factory.get_catalogue()
+    }
+
+    /// Build a unit belonging to no player, for free
+    pub fn build_neutral(&self, unit_type: UnitType) -> Result<Unit, RtsException> {
+        self.unit_factory
+            .build_unit(unit_type, &Upgrades::default())
+    }
+
     pub fn buy_unit<T: TurnStrategyRequester>(
         &self,
         unit_type: UnitType,
         player: &mut Player<T>,
     ) -> Result<Unit, RtsException> {
-        if self.retrieve_money(&unit_type, player) {
-            Ok(self.unit_factory.build_unit(unit_type))?
+        let upgrades = player.get_research().get_upgrades().clone();
+        if self.locked_units.contains(&unit_type) && !upgrades.unlocked.contains(&unit_type) {
+            Err(RtsException::BuyUnitException(
+                unit_type,
+                format!("Player {} has to unlock it first !", player.get_name()),
+            ))
+        } else if self.retrieve_money(&unit_type, player)? {
+            self.unit_factory.build_unit(unit_type, &upgrades)
         } else {
             Err(RtsException::BuyUnitException(
                 unit_type,
@@ -60,14 +91,17 @@ impl Barrack {
         &self,
         unit_type: &UnitType,
         player: &mut Player<T>,
-    ) -> bool {
-        player.update_money(-unit_type.get_cost() as i32).is_some()
+    ) -> Result<bool, RtsException> {
+        let cost = self.get_catalogue().get_cost(unit_type)?;
+        let cost = player.get_research().get_upgrades().get_unit_cost(cost);
+        Ok(player.update_money(-cost as i32).is_some())
     }
 }
 
 #[cfg(test)]
 mod test_building {
-    use super::Barrack;
+    use super::{Bank, Barrack};
+    use crate::components::tech_tree::TechTree;
     use crate::components::turn_strategy::TurnStrategy;
     use crate::entity::player::Player;
     use crate::entity::unit::UnitType;
@@ -85,4 +119,23 @@ mod test_building {
             assert!(false);
         }
     }
+
+    #[test]
+    pub fn should_apply_research() {
+        let tree = TechTree::default();
+        let mut player = Player::new(String::from("Tigran"), TurnStrategy::AI);
+        player.update_money(100);
+        let research = player.get_research_mut();
+        for technology in ["drill_sergeants", "trade_routes"] {
+            research.start(&tree, technology).unwrap();
+            while research.progress(&tree).is_none() {}
+        }
+        let barrack = Barrack::default().with_locked_units(tree.get_locked_units());
+
+        assert!(barrack.buy_unit(UnitType::Mage, &mut player).is_err());
+        barrack.buy_unit(UnitType::Classic, &mut player).unwrap();
+        assert_eq!(&84, player.get_money());
+        Bank::give_money(&mut player, 100).unwrap();
+        assert_eq!(&209, player.get_money());
+    }
 }
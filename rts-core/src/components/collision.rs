@@ -0,0 +1,175 @@
+use crate::components::map::Map;
+use crate::components::play_ground::Coordinate;
+use crate::entity::order::MoveOrder;
+
+/// Room taken on the ground by a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    pub coordinate: Coordinate,
+    /// In thousandths of a tile
+    pub radius: i32,
+}
+
+impl Footprint {
+    /// True if a unit of the radius standing at the coordinate would touch this one
+    pub fn overlaps(&self, coordinate: &Coordinate, radius: i32) -> bool {
+        let reach = (self.radius + radius) as i64;
+        self.coordinate.squared_distance(coordinate) < reach * reach
+    }
+}
+
+/// True if a unit may go from one place to the other without ending on another unit.
+/// Units already touching may still move away from each other.
+pub fn is_clear(from: &Coordinate, to: &Coordinate, radius: i32, others: &[Footprint]) -> bool {
+    others.iter().all(|other| {
+        !other.overlaps(to, radius)
+            || other.coordinate.squared_distance(to) >= other.coordinate.squared_distance(from)
+    })
+}
+
+/// Walk the order as far as the budget allows, stopping before another unit.
+/// Returns where the unit stops, the order is left as it was if the unit can not move.
+pub fn walk_clear(
+    order: &mut MoveOrder,
+    from: Coordinate,
+    budget: i64,
+    radius: i32,
+    others: &[Footprint],
+) -> Coordinate {
+    let mut reached = None;
+    // Checked step by step, steps no longer than the smallest radius
+    // so that even fast units never walk through another one
+    let smallest = others
+        .iter()
+        .map(|other| other.radius)
+        .fold(radius, i32::min)
+        .max(1) as i64;
+    let steps = ((budget + smallest - 1) / smallest).max(1);
+    for step in 1..=steps {
+        let mut attempt = order.clone();
+        let to = attempt.advance(from, budget * step / steps);
+        if !is_clear(&from, &to, radius, others) {
+            break;
+        }
+        reached = Some((to, attempt));
+    }
+    match reached {
+        Some((to, attempt)) => {
+            *order = attempt;
+            to
+        }
+        None => from,
+    }
+}
+
+/// Center of the closest walkable tile around the center where a unit fits,
+/// looking ring after ring from `first_ring` tiles away, row by row.
+/// Centers out of the map are brought back to its closest edge first.
+pub fn find_free_spot(
+    map: &Map,
+    center: &Coordinate,
+    first_ring: i32,
+    radius: i32,
+    others: &[Footprint],
+) -> Option<Coordinate> {
+    let (width, height) = (*map.get_width() as i32, *map.get_height() as i32);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (cx, cy) = center.to_tile();
+    let (cx, cy) = (cx.clamp(0, width - 1), cy.clamp(0, height - 1));
+    let last_ring = width.max(height);
+    for ring in first_ring..=last_ring {
+        for dy in -ring..=ring {
+            for dx in -ring..=ring {
+                if dx.abs().max(dy.abs()) != ring {
+                    continue;
+                }
+                let (x, y) = (cx + dx, cy + dy);
+                if x < 0 || y < 0 || !map.is_walkable(x as u32, y as u32) {
+                    continue;
+                }
+                let spot = Coordinate::from_tiles(x, y);
+                if others.iter().all(|other| !other.overlaps(&spot, radius)) {
+                    return Some(spot);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_collision {
+
+    use crate::components::map::{Map, Terrain};
+    use crate::components::play_ground::Coordinate;
+    use crate::entity::order::MoveOrder;
+
+    use super::{find_free_spot, walk_clear, Footprint};
+
+    #[test]
+    pub fn should_stop_before_other_units() {
+        let blocker = Footprint {
+            coordinate: Coordinate::from_tiles(3, 0),
+            radius: 500,
+        };
+        let destination = Coordinate::from_tiles(6, 0);
+        let mut order = MoveOrder::new(destination, vec![destination]);
+
+        let to = walk_clear(&mut order, Coordinate::default(), 4000, 500, &[blocker]);
+        assert_eq!(Coordinate::from_tiles(2, 0), to);
+        assert!(!order.is_done());
+
+        // Already touching, it can only step back
+        let mut back = MoveOrder::new(Coordinate::default(), vec![Coordinate::default()]);
+        let from = Coordinate::new(2500, 0);
+        assert_eq!(
+            Coordinate::default(),
+            walk_clear(&mut back, from, 4000, 500, &[blocker])
+        );
+        let mut forward = MoveOrder::new(destination, vec![destination]);
+        assert_eq!(from, walk_clear(&mut forward, from, 250, 500, &[blocker]));
+    }
+
+    #[test]
+    pub fn should_not_let_fast_units_walk_through_small_ones() {
+        let blocker = Footprint {
+            coordinate: Coordinate::from_tiles(3, 0),
+            radius: 100,
+        };
+        let destination = Coordinate::from_tiles(16, 0);
+        let mut order = MoveOrder::new(destination, vec![destination]);
+
+        let to = walk_clear(&mut order, Coordinate::default(), 16000, 100, &[blocker]);
+        assert_eq!(Coordinate::new(2800, 0), to);
+    }
+
+    #[test]
+    pub fn should_find_free_spot_around_center() {
+        let mut map = Map::new(5, 5);
+        map.set_terrain(1, 1, Terrain::Water);
+        let center = Coordinate::from_tiles(2, 2);
+        let others = [Footprint {
+            coordinate: Coordinate::from_tiles(2, 1),
+            radius: 300,
+        }];
+
+        assert_eq!(
+            Some(Coordinate::from_tiles(3, 1)),
+            find_free_spot(&map, &center, 1, 300, &others)
+        );
+        assert_eq!(Some(center), find_free_spot(&map, &center, 0, 300, &others));
+
+        // Far out of the map, the closest corner is taken
+        map.set_terrain(4, 0, Terrain::Wall);
+        assert_eq!(
+            Some(Coordinate::from_tiles(3, 0)),
+            find_free_spot(&map, &Coordinate::new(i32::MAX, i32::MIN), 0, 300, &others)
+        );
+        assert_eq!(
+            None,
+            find_free_spot(&Map::new(0, 0), &center, 0, 300, &others)
+        );
+    }
+}
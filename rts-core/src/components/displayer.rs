@@ -1,24 +1,16 @@
-use std::fmt::Display;
-
+use crate::components::ascii_renderer::AsciiRenderer;
+use crate::components::snapshot::Snapshot;
 use crate::exceptions::RtsException;
 
-use super::play_ground::{HasIdentifier, PlayGround};
-
-pub trait Displayer<T>
-where
-    T: Display + HasIdentifier,
-{
-    fn display(play_ground: &PlayGround<T>) -> Result<(), RtsException>;
+pub trait Displayer {
+    fn display(&self, snapshot: &Snapshot) -> Result<(), RtsException>;
 }
 
 pub struct ConsoleDisplayer;
 
-impl<T> Displayer<T> for ConsoleDisplayer
-where
-    T: Display + HasIdentifier,
-{
-    fn display(play_ground: &PlayGround<T>) -> Result<(), RtsException> {
-        println!("=== PlayGround === \n{}\n ======", play_ground); 
+impl Displayer for ConsoleDisplayer {
+    fn display(&self, snapshot: &Snapshot) -> Result<(), RtsException> {
+        println!("{}", AsciiRenderer::render(snapshot));
         Ok(())
     }
 }
@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::io::Write;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::components::game::GameStateObserver;
+use crate::components::game_config::GameInfo;
+use crate::entity::game_event::Notification;
+
+/// A line of the match log, the first one is always the header
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum LogLine<'a> {
+    Header(&'a GameInfo),
+    Event(&'a Notification),
+}
+
+/// Write every game event as one JSON object per line (JSON Lines).
+/// The first line describes the match: engine version, config and players.
+pub struct JsonLinesObserver<W>
+where
+    W: Write,
+{
+    sink: RefCell<W>,
+}
+
+impl<W> JsonLinesObserver<W>
+where
+    W: Write,
+{
+    pub fn new(sink: W) -> Self {
+        JsonLinesObserver {
+            sink: RefCell::new(sink),
+        }
+    }
+
+    /// Give back the sink, once the game is over
+    pub fn into_inner(self) -> W {
+        self.sink.into_inner()
+    }
+
+    fn write_line(&self, line: &LogLine) {
+        let mut sink = self.sink.borrow_mut();
+        let written = serde_json::to_writer(&mut *sink, line)
+            .map_err(|e| e.to_string())
+            .and_then(|_| writeln!(sink).map_err(|e| e.to_string()))
+            .and_then(|_| sink.flush().map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to write game event log: {}", e);
+        }
+    }
+}
+
+impl<W> GameStateObserver for JsonLinesObserver<W>
+where
+    W: Write,
+{
+    fn start(&self, info: &GameInfo) {
+        self.write_line(&LogLine::Header(info));
+    }
+
+    fn update(&self, notification: &Notification) {
+        self.write_line(&LogLine::Event(notification));
+    }
+}
+
+#[cfg(test)]
+mod test_event_log {
+
+    use serde_json::Value;
+
+    use crate::components::game::GameStateObserver;
+    use crate::components::game_config::{GameConfig, GameInfo};
+    use crate::entity::game_event::{GameEvent, Notification};
+
+    use super::JsonLinesObserver;
+
+    #[test]
+    pub fn should_write_header_then_one_line_per_event() {
+        let observer = JsonLinesObserver::new(Vec::new());
+        let info = GameInfo::new(GameConfig::default(), vec!["Tigran".to_string()]);
+        observer.start(&info);
+        observer.update(&Notification::new(0, 1, GameEvent::TurnStarted));
+        observer.update(&Notification::new(
+            1,
+            1,
+            GameEvent::MoneyChanged {
+                player: 0,
+                amount: 100,
+                money: 100,
+            },
+        ));
+
+        let log = String::from_utf8(observer.into_inner()).unwrap();
+        let lines: Vec<Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(3, lines.len());
+        assert_eq!("header", lines[0]["kind"]);
+        assert_eq!("Tigran", lines[0]["players"][0]);
+        assert_eq!(100, lines[0]["config"]["money_batch"]);
+        assert_eq!("event", lines[2]["kind"]);
+        assert_eq!(1, lines[2]["sequence"]);
+        assert_eq!("MoneyChanged", lines[2]["event"]["type"]);
+    }
+}
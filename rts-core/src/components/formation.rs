@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::{distance, Coordinate, TILE_SCALE};
+
+/// Shape kept by a group of units walking together
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Formation {
+    /// Side by side, across the way
+    #[default]
+    Line,
+    /// Rows of the same width, one behind the other
+    Box,
+    /// A leader in front, the others on both sides behind
+    Wedge,
+}
+
+impl Formation {
+    /// Place of each member, in tiles forward and to the right of the group center
+    fn offsets(&self, count: usize) -> Vec<(i64, i64)> {
+        let count = count as i64;
+        match self {
+            Formation::Line => (0..count).map(|i| (0, i * 2 - (count - 1))).collect(),
+            Formation::Box => {
+                let width = (1..=count).find(|side| side * side >= count).unwrap_or(1);
+                (0..count)
+                    .map(|i| (-(i / width) * 2, (i % width) * 2 - (width - 1)))
+                    .collect()
+            }
+            Formation::Wedge => (0..count)
+                .map(|i| {
+                    let row = (i + 1) / 2;
+                    let side = if i % 2 == 1 { -1 } else { 1 };
+                    (-row * 2, side * row * 2)
+                })
+                .collect(),
+        }
+    }
+
+    /// Where each member goes when the group walks from one point to the other,
+    /// facing the way it walks. Offsets are counted in half tiles.
+    pub fn slots(&self, count: usize, from: &Coordinate, to: &Coordinate) -> Vec<Coordinate> {
+        let scale = TILE_SCALE as i64;
+        let (dx, dy) = (to.x as i64 - from.x as i64, to.y as i64 - from.y as i64);
+        // Only the heading matters, shrunk so that its square stays in range
+        let shrink = dx.abs().max(dy.abs()) / (1 << 30) + 1;
+        let heading = Coordinate::new((dx / shrink) as i32, (dy / shrink) as i32);
+        let length = distance(&Coordinate::default(), &heading);
+        let (forward_x, forward_y) = if length == 0 {
+            (scale, 0)
+        } else {
+            (
+                heading.x as i64 * scale / length,
+                heading.y as i64 * scale / length,
+            )
+        };
+        // Right hand side of the walk, y grows downward
+        let (right_x, right_y) = (-forward_y, forward_x);
+        self.offsets(count)
+            .into_iter()
+            .map(|(forward, right)| {
+                Coordinate::new(
+                    (to.x as i64 + (forward * forward_x + right * right_x) / 2)
+                        .clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+                    (to.y as i64 + (forward * forward_y + right * right_y) / 2)
+                        .clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_formation {
+
+    use crate::components::game::test_game::{duel, walk, TestClientGameState};
+    use crate::components::play_ground::Coordinate;
+    use crate::entity::game_actions::{Action, UnitSelection};
+    use crate::entity::unit::UnitType;
+
+    use super::Formation;
+
+    #[test]
+    pub fn should_place_members_facing_the_walk() {
+        let from = Coordinate::default();
+        let to = Coordinate::from_tiles(5, 0);
+        assert_eq!(
+            vec![
+                Coordinate::from_tiles(5, -1),
+                Coordinate::from_tiles(5, 0),
+                Coordinate::from_tiles(5, 1)
+            ],
+            Formation::Line.slots(3, &from, &to)
+        );
+        assert_eq!(
+            vec![
+                Coordinate::new(5000, -500),
+                Coordinate::new(5000, 500),
+                Coordinate::new(4000, -500),
+                Coordinate::new(4000, 500)
+            ],
+            Formation::Box.slots(4, &from, &to)
+        );
+        assert_eq!(
+            vec![
+                to,
+                Coordinate::from_tiles(4, -1),
+                Coordinate::from_tiles(4, 1)
+            ],
+            Formation::Wedge.slots(3, &from, &to)
+        );
+    }
+
+    #[test]
+    pub fn should_place_members_at_the_limits_of_coordinates() {
+        let from = Coordinate::new(i32::MIN, 0);
+        let to = Coordinate::new(i32::MAX, 0);
+        assert_eq!(
+            vec![
+                Coordinate::new(i32::MAX, -1000),
+                to,
+                Coordinate::new(i32::MAX, 1000)
+            ],
+            Formation::Line.slots(3, &from, &to)
+        );
+        assert_eq!(
+            vec![Coordinate::new(i32::MAX, 0)],
+            Formation::Wedge.slots(1, &to, &Coordinate::new(i32::MAX, 0))
+        );
+    }
+
+    #[test]
+    pub fn should_move_groups_in_formation() {
+        let game = duel(TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::BuyUnit(UnitType::Knight)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        assert!(game
+            .play(0, Action::AssignGroup("army".to_string(), vec![1, 3]))
+            .is_err());
+        game.play(0, Action::AssignGroup("army".to_string(), vec![2, 1]))
+            .unwrap();
+        assert!(game
+            .play(
+                0,
+                Action::HoldGroup(UnitSelection::Group("navy".to_string()))
+            )
+            .is_err());
+
+        let destination = Coordinate::from_tiles(10, 6);
+        let army = UnitSelection::Group("army".to_string());
+        game.play(0, Action::MoveGroup(army, destination, Formation::Line))
+            .unwrap();
+        let start = game.observe(0).unwrap().units;
+        game.execute_orders().unwrap();
+        let observation = game.observe(0).unwrap();
+        // The classic keeps in step with the slower knight
+        for (before, after) in start.iter().zip(observation.units.iter()) {
+            assert!(after.coordinate.is_within(&before.coordinate, 2));
+        }
+        assert_eq!(vec![1, 2], observation.groups["army"]);
+
+        walk(&game);
+        let center = Coordinate::new(500, 0);
+        let slots = Formation::Line.slots(2, &center, &destination);
+        let reached: Vec<Coordinate> = game
+            .observe(0)
+            .unwrap()
+            .units
+            .iter()
+            .map(|placed| placed.coordinate)
+            .collect();
+        assert_eq!(slots, reached);
+    }
+}
@@ -1,26 +1,66 @@
+mod combat;
+
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::thread;
 use std::time::Duration;
 
+use log::debug;
+
 use crate::components::building::{Bank, Barrack};
+use crate::components::collision::{find_free_spot, walk_clear, Footprint};
 use crate::components::displayer::{ConsoleDisplayer, Displayer};
-use crate::components::play_ground::{Coordinate, Identifier, PlayGround, PlayGroundObserver};
+use crate::components::formation::Formation;
+use crate::components::game_config::{GameConfig, GameInfo};
+use crate::components::map::{BuildingKind, Map};
+use crate::components::match_result::{MatchResult, ScoreSource};
+use crate::components::observation::{Observation, OrderSnapshot};
+use crate::components::pathfinding::find_path;
+use crate::components::play_ground::{
+    Cell, Coordinate, HasIdentifier, Identifier, PlayGround, PlayGroundObserver, TILE_SCALE,
+};
+use crate::components::replay::{Replay, ReplayPlayer};
+use crate::components::rng::GameRng;
+use crate::components::scenario::{Condition, Effect, Objective, ScenarioOutcome, ScenarioUnit};
+use crate::components::snapshot::{AttackSnapshot, PlayerSnapshot, Snapshot, UnitSnapshot};
+use crate::components::state_hash::{StateHash, StateHasher};
+use crate::components::tech_tree::Upgrade;
 use crate::components::turn_strategy::TurnStrategy;
-use crate::entity::game_actions::{Action, MoveState};
-use crate::entity::player::Player;
+use crate::components::visibility::{PlayerVision, Sight, VisibilityMap};
+use crate::entity::game_actions::{Action, MoveState, UnitSelection};
+use crate::entity::game_event::{EventQueue, GameEvent, Notification};
+use crate::entity::order::{MoveOrder, Order, OrderKind};
+use crate::entity::player::{GroupName, Player, PlayerIndex};
 use crate::entity::unit::{Unit, UnitType};
 use crate::exceptions::RtsException;
 
 type InnerPlayer = Rc<RefCell<Player<TurnStrategy>>>;
 type InnerMoveState = Rc<RefCell<Vec<MoveState>>>;
 type InnerUnitsPlayGround = Rc<RefCell<PlayGround<Unit>>>;
+type InnerEventQueue = Rc<RefCell<EventQueue>>;
+type InnerReplay = Rc<RefCell<Replay>>;
+type InnerAttacks = Rc<RefCell<Vec<AttackSnapshot>>>;
+type InnerRng = Rc<RefCell<GameRng>>;
+type InnerVisions = Rc<RefCell<Vec<PlayerVision>>>;
+type InnerCaptures = Rc<RefCell<Vec<Option<PlayerIndex>>>>;
+type InnerTriggers = Rc<RefCell<Vec<bool>>>;
 
-const TURN_DURATION_IN_SECONDS: u64 = 10;
-
+/// Followers walk again once their target is farther, in tiles
+const FOLLOW_DISTANCE: i16 = 2;
 /// Public hooks for clients to be update on game state.
+/// Each notification is given once, in the order events happened.
 pub trait GameStateObserver {
-    fn update(&self, m: &MoveState);
+    /// Called once, before the first turn is played
+    fn start(&self, _info: &GameInfo) {}
+
+    fn update(&self, notification: &Notification);
+
+    /// Player whose point of view the client takes, events hidden to that player
+    /// by the fog of war are not given. None to see everything.
+    fn viewpoint(&self) -> Option<PlayerIndex> {
+        None
+    }
 }
 
 /// Our RTS game is represented by this structure.
@@ -28,10 +68,25 @@ pub struct Game<StateClient>
 where
     StateClient: GameStateObserver,
 {
+    config: GameConfig,
     barrack: Barrack,
     players: Vec<InnerPlayer>,
     moves: InnerMoveState,
     map: InnerUnitsPlayGround,
+    /// Events waiting to be sent to observers
+    events: InnerEventQueue,
+    /// Actions requested by players, to play the game again
+    replay: InnerReplay,
+    /// Attacks of the current turn, for displayers
+    attacks: InnerAttacks,
+    /// Only source of randomness of the game, seeded by the config
+    rng: InnerRng,
+    /// What each player sees and remembers, updated at the end of each turn
+    visions: InnerVisions,
+    /// Owner of each capture point of the map
+    captures: InnerCaptures,
+    /// Scenario triggers which already fired
+    fired_triggers: InnerTriggers,
     /// External clients wanting notifications on game state
     game_state_observers: Vec<StateClient>,
 }
@@ -42,71 +97,576 @@ where
 {
     /// Create a new game with the given players and clients wanting notifications
     pub fn new(players: Vec<Player<TurnStrategy>>, game_state_observers: Vec<StateClient>) -> Self {
+        Self::with_config(players, game_state_observers, GameConfig::default())
+    }
+
+    /// Create a new game playing with the given rules
+    pub fn with_config(
+        players: Vec<Player<TurnStrategy>>,
+        game_state_observers: Vec<StateClient>,
+        config: GameConfig,
+    ) -> Self {
+        let replay_players = players
+            .iter()
+            .map(|player| ReplayPlayer {
+                name: player.get_name().to_string(),
+                money: *player.get_money(),
+            })
+            .collect();
+        let replay = Replay::new(config.clone(), replay_players);
+        let rng = GameRng::new(config.seed);
+        let visions = vec![PlayerVision::default(); players.len()];
         let players: Vec<InnerPlayer> = players
             .into_iter()
             .map(|player| Rc::new(RefCell::new(player)))
             .collect();
-        Game {
-            barrack: Barrack::default(),
+        let captures = vec![None; config.map.get_capture_points().len()];
+        let triggers = config
+            .scenario
+            .as_ref()
+            .map(|scenario| scenario.triggers.len())
+            .unwrap_or_default();
+        let game = Game {
+            barrack: Barrack::with_catalogue(config.catalogue.clone())
+                .with_locked_units(config.tech_tree.get_locked_units()),
+            config,
             players,
             moves: Rc::new(RefCell::new(Vec::new())),
             map: Rc::new(RefCell::new(PlayGround::default())),
+            events: Rc::new(RefCell::new(EventQueue::default())),
+            replay: Rc::new(RefCell::new(replay)),
+            attacks: Rc::new(RefCell::new(Vec::new())),
+            rng: Rc::new(RefCell::new(rng)),
+            visions: Rc::new(RefCell::new(visions)),
+            captures: Rc::new(RefCell::new(captures)),
+            fired_triggers: Rc::new(RefCell::new(vec![false; triggers])),
             game_state_observers,
+        };
+        game.spawn_creeps();
+        if let Some(scenario) = &game.config.scenario {
+            for placed in scenario.units.iter() {
+                game.spawn_scenario_unit(placed);
+            }
+        }
+        game
+    }
+
+    /// Put the neutral units of the map in their camps
+    fn spawn_creeps(&self) {
+        for camp in self.config.map.get_creeps() {
+            // Unknown unit types are left out of the game
+            if let Ok(creep) = self.barrack.build_neutral(camp.unit_type) {
+                let coordinate = self.free_spot(&camp.coordinate, *creep.get_radius());
+                self.place_unit(creep, coordinate);
+            }
+        }
+    }
+
+    /// Put a unit of a scenario on the map, returns where it stands.
+    /// Unknown unit types are left out of the game.
+    fn spawn_scenario_unit(&self, placed: &ScenarioUnit) -> Option<(Identifier, Coordinate)> {
+        // Whoever owns them, scenario units start without upgrades
+        let mut unit = self.barrack.build_neutral(placed.unit_type).ok()?;
+        if let Some(owner) = placed.owner {
+            unit.set_owner(owner);
         }
+        let coordinate = self.free_spot(&placed.coordinate, *unit.get_radius());
+        if let Some(destination) = placed.attack_move {
+            if let Some(path) = find_path(&self.config.map, &coordinate, &destination) {
+                unit.set_order(Some(Order::AttackMove(MoveOrder::new(destination, path))));
+            }
+        }
+        let identifier = unit.get_identifier();
+        self.place_unit(unit, coordinate);
+        Some((identifier, coordinate))
+    }
+
+    /// Closest place around the coordinate where a unit of the radius fits
+    fn free_spot(&self, coordinate: &Coordinate, radius: i32) -> Coordinate {
+        let others = Self::footprints(&self.map.borrow(), None);
+        find_free_spot(&self.config.map, coordinate, 0, radius, &others).unwrap_or(*coordinate)
+    }
+
+    fn place_unit(&self, unit: Unit, coordinate: Coordinate) {
+        let identifier = unit.get_identifier();
+        let mut play_ground = self.map.borrow_mut();
+        play_ground.update(unit);
+        // The cell was just added, it can always be found
+        let _ = play_ground.update_cell(identifier, coordinate);
+    }
+
+    /// Create a game playing again the actions of a replay
+    pub fn from_replay(replay: &Replay, game_state_observers: Vec<StateClient>) -> Self {
+        Self::with_config(
+            replay.to_players(),
+            game_state_observers,
+            replay.config.clone(),
+        )
+    }
+
+    /// Actions played so far, along with the rules and players of the game
+    pub fn get_replay(&self) -> Replay {
+        self.replay.borrow().clone()
     }
 
     pub fn get_players(&self) -> &[InnerPlayer] {
         &self.players
     }
+
+    pub fn get_config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Random numbers for rules, never use another source of randomness
+    /// or replays will not give the same game again
+    pub fn get_rng(&self) -> InnerRng {
+        Rc::clone(&self.rng)
+    }
+
+    pub fn get_info(&self) -> GameInfo {
+        let names = self
+            .players
+            .iter()
+            .map(|player| player.borrow().get_name().to_string())
+            .collect();
+        GameInfo::new(self.config.clone(), names)
+    }
+
     pub fn console_display(&self) -> Result<(), RtsException> {
+        ConsoleDisplayer.display(&self.snapshot())
+    }
+
+    /// Copy the current state of the game, for displayers
+    pub fn snapshot(&self) -> Snapshot {
+        let units = self.placed_units();
+
+        let players = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| {
+                let player = player.borrow();
+                PlayerSnapshot {
+                    name: player.get_name().to_string(),
+                    money: *player.get_money(),
+                    units: units
+                        .iter()
+                        .filter(|placed| placed.unit.get_owner() == &Some(i))
+                        .count(),
+                    score: *player.get_score(),
+                }
+            })
+            .collect();
+
+        Snapshot {
+            turn: *self.events.borrow().get_turn(),
+            map: self.config.map.clone(),
+            units,
+            players,
+            attacks: self.attacks.borrow().clone(),
+        }
+    }
+
+    /// Fingerprint of players, units, capture points, scenario triggers and random numbers.
+    /// Two games which played the same turns give the same hash.
+    pub fn state_hash(&self) -> StateHash {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(*self.events.borrow().get_turn() as u64);
+        for player in self.players.iter() {
+            hasher.write(&*player.borrow());
+        }
+
         let play_ground_ptr = Rc::clone(&self.map);
         let play_ground = play_ground_ptr.borrow();
-        ConsoleDisplayer::display(&play_ground)
+        let mut cells: Vec<_> = play_ground
+            .get_cells()
+            .iter()
+            .map(|cell| cell.borrow())
+            .collect();
+        // Cells order depends on the history of the play ground, not units
+        cells.sort_by_key(|cell| cell.get().map(|unit| unit.get_identifier()));
+        for cell in cells.iter() {
+            if let Some(unit) = cell.get() {
+                hasher.write(unit);
+                hasher.write(cell.get_coordinate());
+            }
+        }
+        for owner in self.captures.borrow().iter() {
+            hasher.write_i64(owner.map(|owner| owner as i64).unwrap_or(-1));
+        }
+        for fired in self.fired_triggers.borrow().iter() {
+            hasher.write_u64(*fired as u64);
+        }
+
+        hasher.write(&*self.rng.borrow());
+        hasher.finish()
+    }
+
+    /// What the player knows of the game: its own units, enemies in sight
+    /// and where hidden enemies were last seen
+    pub fn observe(&self, player: PlayerIndex) -> Result<Observation, RtsException> {
+        let money = *self.get_player(player)?.borrow().get_money();
+        let groups = self.get_player(player)?.borrow().get_groups().clone();
+        let captures = self.captures.borrow().clone();
+        let research = self
+            .players
+            .iter()
+            .map(|player| player.borrow().get_research().clone())
+            .collect();
+        let placed_units = self.placed_units();
+        let visibility = self.visibility_of(player, &placed_units);
+        let (units, enemies) = placed_units
+            .into_iter()
+            .partition::<Vec<UnitSnapshot>, _>(|placed| placed.unit.get_owner() == &Some(player));
+        let (allies, enemies): (Vec<UnitSnapshot>, Vec<UnitSnapshot>) = enemies
+            .into_iter()
+            .partition(|placed| self.is_ally(player, placed.unit.get_owner()));
+        let orders = units
+            .iter()
+            .filter_map(|placed| {
+                placed.unit.get_order().as_ref().map(|order| {
+                    let path = order.get_path();
+                    OrderSnapshot {
+                        identifier: placed.unit.get_identifier(),
+                        order: order.get_kind(),
+                        destination: path.map(|path| *path.get_destination()),
+                        eta: path.and_then(|path| {
+                            path.get_eta(&placed.coordinate, placed.unit.get_effective_speed())
+                        }),
+                    }
+                })
+            })
+            .collect();
+        let enemies = enemies
+            .into_iter()
+            .filter(|placed| visibility.is_visible(&placed.coordinate))
+            .collect();
+        let last_known = self
+            .visions
+            .borrow()
+            .get(player)
+            .map(|vision| vision.get_last_known(&visibility))
+            .unwrap_or_default();
+        Ok(Observation {
+            turn: *self.events.borrow().get_turn(),
+            player,
+            money,
+            units,
+            enemies,
+            allies,
+            last_known,
+            visibility,
+            research,
+            orders,
+            captures,
+            groups,
+        })
+    }
+
+    /// Units on the map, sorted by identifier
+    fn placed_units(&self) -> Vec<UnitSnapshot> {
+        let play_ground_ptr = Rc::clone(&self.map);
+        let play_ground = play_ground_ptr.borrow();
+        let mut units: Vec<UnitSnapshot> = play_ground
+            .get_cells()
+            .iter()
+            .filter_map(|cell| {
+                let cell = cell.borrow();
+                cell.get().map(|unit| UnitSnapshot {
+                    unit: unit.clone(),
+                    coordinate: *cell.get_coordinate(),
+                })
+            })
+            .collect();
+        units.sort_by_key(|placed| placed.unit.get_identifier());
+        units
+    }
+
+    /// Units and buildings of the player looking around
+    fn sights_of(&self, player: PlayerIndex, units: &[UnitSnapshot]) -> Vec<Sight> {
+        let buildings = (0..self.players.len())
+            .filter(|ally| self.config.are_allies(player, *ally))
+            .flat_map(|ally| Self::building_sights(&self.config.map, ally));
+        units
+            .iter()
+            .filter(|placed| self.is_ally(player, placed.unit.get_owner()))
+            .map(|placed| Sight {
+                coordinate: placed.coordinate,
+                radius: self
+                    .config
+                    .catalogue
+                    .get_vision(placed.unit.get_unit_type()),
+            })
+            .chain(buildings)
+            .collect()
+    }
+
+    /// What the player and its allies see of the map now
+    fn visibility_of(&self, player: PlayerIndex, units: &[UnitSnapshot]) -> VisibilityMap {
+        VisibilityMap::compute(&self.config.map, &self.sights_of(player, units))
+    }
+
+    /// Where the player believes the unit is: where it stands when in sight,
+    /// where it was last seen otherwise
+    fn believed_position(
+        &self,
+        player: PlayerIndex,
+        identifier: Identifier,
+        units: &[UnitSnapshot],
+        visibility: &VisibilityMap,
+    ) -> Option<Coordinate> {
+        let seen = units
+            .iter()
+            .find(|placed| placed.unit.get_identifier() == identifier)
+            .map(|placed| placed.coordinate)
+            .filter(|coordinate| visibility.is_visible(coordinate));
+        seen.or_else(|| {
+            self.visions
+                .borrow()
+                .get(player)
+                .and_then(|vision| vision.get_remembered(&identifier))
+                .map(|known| known.coordinate)
+        })
+    }
+
+    fn building_sights(map: &Map, player: PlayerIndex) -> Vec<Sight> {
+        map.get_buildings()
+            .iter()
+            .filter(|building| building.owner == player)
+            .map(|building| Sight {
+                coordinate: building.coordinate,
+                radius: building.kind.get_vision(),
+            })
+            .collect()
+    }
+
+    /// Players owning one of the units or seeing one of the coordinates
+    fn audience(
+        &self,
+        owners: &[Option<PlayerIndex>],
+        coordinates: &[Coordinate],
+    ) -> Vec<PlayerIndex> {
+        let units = self.placed_units();
+        (0..self.players.len())
+            .filter(|player| {
+                owners.iter().any(|owner| self.is_ally(*player, owner))
+                    || self.sights_of(*player, &units).iter().any(|sight| {
+                        coordinates
+                            .iter()
+                            .any(|coordinate| sight.sees(&self.config.map, coordinate))
+                    })
+            })
+            .collect()
+    }
+
+    /// Compute again what each player sees and remember enemies in sight
+    pub(crate) fn update_visions(&self) {
+        let turn = *self.events.borrow().get_turn();
+        let units = self.placed_units();
+        let mut visions = self.visions.borrow_mut();
+        for (player, vision) in visions.iter_mut().enumerate() {
+            let visibility = self.visibility_of(player, &units);
+            let enemies: Vec<UnitSnapshot> = units
+                .iter()
+                .filter(|placed| !self.is_ally(player, placed.unit.get_owner()))
+                .cloned()
+                .collect();
+            vision.update(turn, visibility, &enemies);
+        }
+    }
+
+    /// Scores of the players so far and who is leading
+    pub fn match_result(&self) -> MatchResult {
+        let scores = self
+            .players
+            .iter()
+            .map(|player| *player.borrow().get_score())
+            .collect();
+        let breakdowns = self
+            .players
+            .iter()
+            .map(|player| player.borrow().get_breakdown().clone())
+            .collect();
+        let teams = (0..self.players.len())
+            .map(|player| self.config.team_of(player))
+            .collect();
+        MatchResult::from_team_scores(scores, teams).with_breakdowns(breakdowns)
     }
 
     /// Events loop to handle game state
     pub fn start(&self) -> Result<(), RtsException> {
         loop {
-            self.execute_recurring_actions()?;
-            self.play_with_all_players()?;
-            self.update_observers()?;
-            if self.check_game_is_over()? {
+            if self.play_turn()? {
                 break;
             }
 
-            thread::sleep(Duration::from_secs(TURN_DURATION_IN_SECONDS));
+            thread::sleep(Duration::from_secs(self.config.turn_duration_in_seconds));
+        }
+        Ok(())
+    }
+
+    /// Play a single turn, returns true when the game is over
+    pub fn play_turn(&self) -> Result<bool, RtsException> {
+        self.begin_turn()?;
+        self.fire_triggers()?;
+        self.execute_recurring_actions()?;
+        self.play_with_all_players()?;
+        self.execute_orders()?;
+        self.command_creeps()?;
+        self.hold_capture_points()?;
+        let is_over = self.end_turn()?;
+        self.update_observers()?;
+        Ok(is_over)
+    }
+
+    pub(crate) fn begin_turn(&self) -> Result<(), RtsException> {
+        let events_ptr = Rc::clone(&self.events);
+        let turn = events_ptr.borrow_mut().next_turn();
+        self.attacks.borrow_mut().clear();
+        if turn == 1 {
+            let info = self.get_info();
+            self.game_state_observers
+                .iter()
+                .for_each(|client| client.start(&info));
+        }
+        self.notify(GameEvent::TurnStarted);
+        self.progress_research()?;
+
+        let elapsed = (self.config.turn_duration_in_seconds * 1000) as i32;
+        let mut ticks = Vec::new();
+        {
+            let play_ground_ptr = Rc::clone(&self.map);
+            let play_ground = play_ground_ptr.borrow();
+            for cell in play_ground.get_cells() {
+                let mut holder = cell.borrow_mut();
+                let coordinate = *holder.get_coordinate();
+                if let Some(unit) = holder.get_mut() {
+                    unit.cool_down_skills(elapsed);
+                    let tick = unit.tick_effects()?;
+                    ticks.push((unit.clone(), coordinate, tick));
+                }
+            }
+        }
+
+        for (unit, coordinate, tick) in ticks {
+            let identifier = unit.get_identifier();
+            let audience = self.audience(&[*unit.get_owner()], &[coordinate]);
+            for effect in tick.expired {
+                self.notify_to(
+                    GameEvent::EffectExpired { identifier, effect },
+                    audience.clone(),
+                );
+            }
+            if tick.damage > 0 {
+                self.notify_to(
+                    GameEvent::EffectDamaged {
+                        identifier,
+                        damage: tick.damage,
+                        remaining_health: *unit.get_health(),
+                    },
+                    audience.clone(),
+                );
+            }
+            if !unit.is_alive() {
+                let (killer, killer_owner) = tick
+                    .poison
+                    .map(|poison| (poison.source.unwrap_or(identifier), poison.source_owner))
+                    .unwrap_or((identifier, None));
+                self.kill_unit(&unit, killer, killer_owner, audience)?;
+            }
         }
         Ok(())
     }
 
+    /// Make research go on, upgrades of finished ones reach the units alive
+    fn progress_research(&self) -> Result<(), RtsException> {
+        for (index, player) in self.players.iter().enumerate() {
+            let researched = player
+                .borrow_mut()
+                .get_research_mut()
+                .progress(&self.config.tech_tree);
+            let technology = match researched {
+                Some(technology) => technology,
+                None => continue,
+            };
+            for upgrade in self.config.tech_tree.get(&technology)?.upgrades.iter() {
+                if let Upgrade::Stats { growth } = upgrade {
+                    let play_ground = self.map.borrow();
+                    for cell in play_ground.get_cells() {
+                        if let Some(unit) = cell.borrow_mut().get_mut() {
+                            if unit.get_owner() == &Some(index) {
+                                unit.grow(growth);
+                            }
+                        }
+                    }
+                }
+            }
+            self.notify(GameEvent::ResearchCompleted {
+                player: index,
+                technology,
+            });
+        }
+        Ok(())
+    }
+
+    fn end_turn(&self) -> Result<bool, RtsException> {
+        self.update_visions();
+        let state_hash = self.state_hash();
+        self.replay.borrow_mut().hashes.push(state_hash);
+        self.notify(GameEvent::TurnEnded { state_hash });
+        let ended = self
+            .scenario_outcome()
+            .filter(|outcome| outcome != &ScenarioOutcome::InProgress);
+        if let Some(outcome) = ended {
+            self.notify(GameEvent::ScenarioEnded { outcome });
+        }
+        let is_over = self.check_game_is_over()? || ended.is_some();
+        if is_over {
+            self.notify(GameEvent::GameOver);
+        }
+        Ok(is_over)
+    }
+
     fn play_with_all_players(&self) -> Result<(), RtsException> {
+        let mut requested = Vec::with_capacity(self.players.len());
         for (i, player) in self.players.iter().enumerate() {
             let player_ptr = Rc::clone(player);
             let action = player_ptr.borrow().request()?;
-            self.play(i, action)?;
+            requested.push(action.clone());
+            // A rejected action only costs the turn of the player, who is told why
+            if let Err(e) = self.play(i, action) {
+                debug!("Action of player {} rejected: {}", i, e);
+            }
         }
 
+        let replay_ptr = Rc::clone(&self.replay);
+        replay_ptr.borrow_mut().turns.push(requested);
         Ok(())
     }
 
-    fn play(&self, index: usize, action: Action) -> Result<(), RtsException> {
+    pub(crate) fn play(&self, index: usize, action: Action) -> Result<(), RtsException> {
         if index >= self.players.len() {
             Err(RtsException::ExecuteActionException(format!(
                 "Failed to find player {} when executing action {}",
                 index,
                 action.get_name()
             )))
-        } else if let Some(player) = self.players.get(index) {
-            println!("Executing action {}", action.get_name());
-            let result = self.execute_action(Rc::clone(player), action)?;
-            self.update_moves_state(result)?;
-            Ok(())
         } else {
-            Err(RtsException::ExecuteActionException(format!(
-                "Failed to execute action {} for player {}",
-                action.get_name(),
-                index
-            )))
+            let name = action.get_name();
+            match self.execute_action(index, action) {
+                Ok(result) => self.update_moves_state(result),
+                Err(e) => {
+                    self.notify_to(
+                        GameEvent::ActionRejected {
+                            player: index,
+                            action: name,
+                            reason: e.to_string(),
+                        },
+                        vec![index],
+                    );
+                    Err(e)
+                }
+            }
         }
     }
 
@@ -125,19 +685,30 @@ where
         Ok(moves_mutex.contains(&MoveState::EndGame))
     }
 
-    fn update_observers(&self) -> Result<(), RtsException> {
-        let moves_ptr = Rc::clone(&self.moves);
-        let moves = moves_ptr.borrow();
-        for m in moves.iter() {
-            if let MoveState::BuyUnit(unit) = m {
-                let unit_clone = unit.clone(); // Clone here should be ok, it will be the stored item
-                let play_ground_ptr = Rc::clone(&self.map);
-                let mut play_ground_mutex = play_ground_ptr.borrow_mut();
-                play_ground_mutex.update(unit_clone);
-                self.game_state_observers
-                    .iter()
-                    .for_each(|client| client.update(m));
-            }
+    fn notify(&self, event: GameEvent) {
+        let events_ptr = Rc::clone(&self.events);
+        events_ptr.borrow_mut().push(event);
+    }
+
+    /// Notify an event hidden by the fog of war to other players
+    fn notify_to(&self, event: GameEvent, audience: Vec<PlayerIndex>) {
+        let events_ptr = Rc::clone(&self.events);
+        events_ptr.borrow_mut().push_for(event, audience);
+    }
+
+    pub(crate) fn update_observers(&self) -> Result<(), RtsException> {
+        let events_ptr = Rc::clone(&self.events);
+        let notifications = events_ptr.borrow_mut().drain();
+        for notification in notifications.iter() {
+            self.game_state_observers
+                .iter()
+                .filter(|client| {
+                    client
+                        .viewpoint()
+                        .map(|player| notification.is_visible_to(player))
+                        .unwrap_or(true)
+                })
+                .for_each(|client| client.update(notification));
         }
 
         Ok(())
@@ -150,70 +721,900 @@ where
         Ok(())
     }
 
+    fn get_player(&self, index: PlayerIndex) -> Result<InnerPlayer, RtsException> {
+        self.players.get(index).map(Rc::clone).ok_or_else(|| {
+            RtsException::ExecuteActionException(format!("Failed to find player {}", index))
+        })
+    }
+
     fn execute_action(
         &self,
-        player: InnerPlayer,
+        index: PlayerIndex,
         action: Action,
     ) -> Result<MoveState, RtsException> {
         match action {
-            Action::BuyUnit(unit_type) => self.buy_unit(unit_type, player),
-            Action::GiveMoneyBatch => self.give_money(player),
+            Action::BuyUnit(unit_type) => self.buy_unit(unit_type, index),
+            Action::GiveMoneyBatch => self.give_money(index),
+            Action::Research(technology) => self.research(index, technology),
+            Action::Wait => Ok(MoveState::Wait),
             Action::EndGame => Ok(MoveState::EndGame),
-            Action::MoveUnit(i, c) => self.move_unit(i, c),
+            Action::MoveUnit(i, c) => self.move_unit(index, i, c),
+            Action::AttackMove(i, c) => self.attack_move(index, i, c),
+            Action::Patrol(i, c) => self.patrol(index, i, c),
+            Action::HoldPosition(i) => self.give_order(index, i, Some(Order::Hold)),
+            Action::Follow(i, target) => self.follow(index, i, target),
+            Action::Stop(i) => self.give_order(index, i, None),
+            Action::AssignGroup(name, units) => self.assign_group(index, name, units),
+            Action::MoveGroup(units, c, formation) => {
+                self.move_group(index, units, c, formation, false)
+            }
+            Action::AttackMoveGroup(units, c, formation) => {
+                self.move_group(index, units, c, formation, true)
+            }
+            Action::HoldGroup(units) => self.order_group(index, units, Some(Order::Hold)),
+            Action::StopGroup(units) => self.order_group(index, units, None),
+            Action::UseSkill(caster, skill, target) => self.use_skill(index, caster, skill, target),
+            Action::UseSkillAt(caster, skill, point) => {
+                self.use_skill_at(index, caster, skill, point)
+            }
+        }
+    }
+
+    /// Give capture points to the player standing alone around them,
+    /// then reward the owners with their income and score
+    pub(crate) fn hold_capture_points(&self) -> Result<(), RtsException> {
+        let units = self.placed_units();
+        for (point, capture) in self.config.map.get_capture_points().iter().enumerate() {
+            let mut present: Vec<PlayerIndex> = units
+                .iter()
+                .filter(|placed| {
+                    placed
+                        .coordinate
+                        .is_within(&capture.coordinate, capture.radius)
+                })
+                .filter_map(|placed| *placed.unit.get_owner())
+                .collect();
+            present.sort_unstable();
+            present.dedup();
+            let owner = self.captures.borrow().get(point).copied().flatten();
+            // Allies hold a point together, the first of them takes it
+            let alone = present
+                .iter()
+                .all(|player| self.config.are_allies(present[0], *player));
+            let held = owner.is_some_and(|owner| {
+                present
+                    .iter()
+                    .all(|player| self.config.are_allies(owner, *player))
+            });
+            if let (Some(player), true, false) = (present.first().copied(), alone, held) {
+                self.captures.borrow_mut()[point] = Some(player);
+                self.notify(GameEvent::PointCaptured { point, player });
+            }
+            let owner = match self.captures.borrow().get(point).copied().flatten() {
+                Some(owner) => owner,
+                None => continue,
+            };
+            let player = self.get_player(owner)?;
+            let mut player = player.borrow_mut();
+            player.score_points(ScoreSource::Objective, capture.score);
+            if capture.income != 0 {
+                player.update_money(capture.income).ok_or_else(|| {
+                    RtsException::UpdatePlayerException("Capture point income".to_string())
+                })?;
+                self.notify_to(
+                    GameEvent::MoneyChanged {
+                        player: owner,
+                        amount: capture.income,
+                        money: *player.get_money(),
+                    },
+                    vec![owner],
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the player of the scenario stands, None when the game plays no scenario
+    pub fn scenario_outcome(&self) -> Option<ScenarioOutcome> {
+        let scenario = self.config.scenario.as_ref()?;
+        let objectives_met = scenario
+            .objectives
+            .iter()
+            .all(|objective| self.is_met(scenario.player, objective));
+        let defeated = scenario
+            .defeats
+            .iter()
+            .any(|condition| self.holds(condition));
+        let turn = *self.events.borrow().get_turn();
+        Some(scenario.outcome(turn, objectives_met, defeated))
+    }
+
+    fn is_met(&self, player: PlayerIndex, objective: &Objective) -> bool {
+        match objective {
+            Objective::DestroyEnemies => !self.placed_units().iter().any(|placed| {
+                placed
+                    .unit
+                    .get_owner()
+                    .is_some_and(|owner| !self.config.are_allies(player, owner))
+            }),
+            Objective::Reach { coordinate, radius } => {
+                self.has_unit_within(player, coordinate, *radius)
+            }
+            Objective::Capture { point } => {
+                let owner = self.captures.borrow().get(*point).copied().flatten();
+                self.is_ally(player, &owner)
+            }
+            Objective::Score { points } => self
+                .players
+                .get(player)
+                .is_some_and(|inner| inner.borrow().get_score() >= points),
+            Objective::Survive { turn } => {
+                *self.events.borrow().get_turn() >= *turn && self.count_units(player) > 0
+            }
+        }
+    }
+
+    fn holds(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Turn { turn } => *self.events.borrow().get_turn() >= *turn,
+            Condition::Reach {
+                player,
+                coordinate,
+                radius,
+            } => self.has_unit_within(*player, coordinate, *radius),
+            Condition::UnitsLeft { player, count } => self.count_units(*player) <= *count,
         }
     }
 
+    fn count_units(&self, player: PlayerIndex) -> usize {
+        self.placed_units()
+            .iter()
+            .filter(|placed| placed.unit.get_owner() == &Some(player))
+            .count()
+    }
+
+    fn has_unit_within(&self, player: PlayerIndex, coordinate: &Coordinate, radius: i16) -> bool {
+        self.placed_units().iter().any(|placed| {
+            placed.unit.get_owner() == &Some(player)
+                && placed.coordinate.is_within(coordinate, radius)
+        })
+    }
+
+    /// Apply once the effects of the scenario triggers whose condition holds
+    fn fire_triggers(&self) -> Result<(), RtsException> {
+        let scenario = match &self.config.scenario {
+            Some(scenario) => scenario,
+            None => return Ok(()),
+        };
+        for (i, trigger) in scenario.triggers.iter().enumerate() {
+            if self.fired_triggers.borrow()[i] || !self.holds(&trigger.condition) {
+                continue;
+            }
+            self.fired_triggers.borrow_mut()[i] = true;
+            for effect in trigger.effects.iter() {
+                self.apply_effect(effect)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_effect(&self, effect: &Effect) -> Result<(), RtsException> {
+        match effect {
+            Effect::Spawn { units } => {
+                for placed in units.iter() {
+                    let spawned = self.spawn_scenario_unit(placed);
+                    // Neutral units come unannounced, like creeps
+                    if let (Some((identifier, coordinate)), Some(player)) = (spawned, placed.owner)
+                    {
+                        self.notify_to(
+                            GameEvent::UnitSpawned {
+                                player,
+                                identifier,
+                                unit_type: placed.unit_type,
+                                coordinate,
+                            },
+                            self.audience(&[Some(player)], &[coordinate]),
+                        );
+                    }
+                }
+            }
+            Effect::GiveMoney { player, amount } => {
+                let inner = self.get_player(*player)?;
+                let mut inner = inner.borrow_mut();
+                inner.update_money(*amount).ok_or_else(|| {
+                    RtsException::UpdatePlayerException("Scenario money".to_string())
+                })?;
+                self.notify_to(
+                    GameEvent::MoneyChanged {
+                        player: *player,
+                        amount: *amount,
+                        money: *inner.get_money(),
+                    },
+                    vec![*player],
+                );
+            }
+            Effect::Message { text } => {
+                self.notify(GameEvent::ScenarioMessage { text: text.clone() })
+            }
+        }
+        Ok(())
+    }
+
+    /// True if the owner of a unit plays in the team of the player, neutral units have no ally
+    fn is_ally(&self, player: PlayerIndex, owner: &Option<PlayerIndex>) -> bool {
+        owner.is_some_and(|owner| self.config.are_allies(player, owner))
+    }
+
+    /// Find a unit on the map which belongs to the player
+    fn find_owned_unit(
+        play_ground: &PlayGround<Unit>,
+        index: PlayerIndex,
+        identifier: Identifier,
+    ) -> Result<Cell<Unit>, RtsException> {
+        let cell = play_ground.find_cell(&identifier).ok_or_else(|| {
+            RtsException::ExecuteActionException(format!("Failed to find unit {}", identifier))
+        })?;
+        let is_owned = cell
+            .borrow()
+            .get()
+            .map(|unit| unit.get_owner() == &Some(index))
+            .unwrap_or(false);
+        if is_owned {
+            Ok(cell)
+        } else {
+            Err(RtsException::ExecuteActionException(format!(
+                "Unit {} does not belong to player {}",
+                identifier, index
+            )))
+        }
+    }
+
+    /// Where a unit of the player stands, if it can be given orders
+    fn orderable_unit(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+    ) -> Result<Coordinate, RtsException> {
+        let play_ground = self.map.borrow();
+        let cell = Self::find_owned_unit(&play_ground, index, identifier)?;
+        let holder = cell.borrow();
+        if holder.get().map(Unit::is_stunned).unwrap_or(false) {
+            return Err(RtsException::ExecuteActionException(format!(
+                "Unit {} is stunned",
+                identifier
+            )));
+        }
+        Ok(*holder.get_coordinate())
+    }
+
+    /// Path for a unit of the player to the coordinate
+    fn path_for(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+        coordinate: Coordinate,
+    ) -> Result<MoveOrder, RtsException> {
+        let from = self.orderable_unit(index, identifier)?;
+        let path = find_path(&self.config.map, &from, &coordinate).ok_or_else(|| {
+            RtsException::ExecuteActionException(format!(
+                "Unit {} can not reach {}",
+                identifier, coordinate
+            ))
+        })?;
+        Ok(MoveOrder::new(coordinate, path))
+    }
+
     /// Available actions to be executed on the game
     fn move_unit(
         &self,
+        index: PlayerIndex,
         identifier: Identifier,
         coordinate: Coordinate,
     ) -> Result<MoveState, RtsException> {
-        let play_ground_ptr = Rc::clone(&self.map);
-        let play_ground_mutex = play_ground_ptr.borrow();
-        play_ground_mutex
-            .update_cell(identifier, coordinate)
-            .map(|_| MoveState::MoveUnit)
+        let path = self.path_for(index, identifier, coordinate)?;
+        self.give_order(index, identifier, Some(Order::Move(path)))
+    }
+
+    fn attack_move(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+        coordinate: Coordinate,
+    ) -> Result<MoveState, RtsException> {
+        let path = self.path_for(index, identifier, coordinate)?;
+        self.give_order(index, identifier, Some(Order::AttackMove(path)))
+    }
+
+    /// Patrol between where the unit stands and the coordinate
+    fn patrol(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+        coordinate: Coordinate,
+    ) -> Result<MoveState, RtsException> {
+        let from = self.orderable_unit(index, identifier)?;
+        let path = self.path_for(index, identifier, coordinate)?;
+        self.give_order(index, identifier, Some(Order::Patrol { from, path }))
+    }
+
+    fn follow(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+        target: Identifier,
+    ) -> Result<MoveState, RtsException> {
+        if target == identifier {
+            return Err(RtsException::ExecuteActionException(format!(
+                "Unit {} can not follow itself",
+                identifier
+            )));
+        }
+        // Hidden units can not be told from missing ones
+        let units = self.placed_units();
+        let visibility = self.visibility_of(index, &units);
+        let visible = units.iter().any(|placed| {
+            placed.unit.get_identifier() == target && visibility.is_visible(&placed.coordinate)
+        });
+        if !visible {
+            return Err(RtsException::ExecuteActionException(format!(
+                "Failed to find unit {}",
+                target
+            )));
+        }
+        let order = Order::Follow { target, path: None };
+        self.give_order(index, identifier, Some(order))
+    }
+
+    /// Replace the order of a unit of the player, no order stops it
+    fn give_order(
+        &self,
+        index: PlayerIndex,
+        identifier: Identifier,
+        order: Option<Order>,
+    ) -> Result<MoveState, RtsException> {
+        let from = self.orderable_unit(index, identifier)?;
+        let path = order.as_ref().and_then(Order::get_path);
+        let destination = path.map(|path| *path.get_destination());
+        let eta = {
+            let play_ground = self.map.borrow();
+            let cell = Self::find_owned_unit(&play_ground, index, identifier)?;
+            let mut holder = cell.borrow_mut();
+            let eta = holder.get().and_then(|unit| {
+                path.and_then(|path| path.get_eta(&from, unit.get_effective_speed()))
+            });
+            if let Some(unit) = holder.get_mut() {
+                unit.set_order(order.clone());
+            }
+            eta
+        };
+        self.notify_to(
+            GameEvent::UnitOrdered {
+                identifier,
+                order: order
+                    .as_ref()
+                    .map(Order::get_kind)
+                    .unwrap_or(OrderKind::Stop),
+                destination,
+                eta,
+            },
+            vec![index],
+        );
+        Ok(MoveState::GiveOrder)
+    }
+
+    /// Name a set of units of the player, or forget the name when there are none
+    fn assign_group(
+        &self,
+        index: PlayerIndex,
+        name: GroupName,
+        units: Vec<Identifier>,
+    ) -> Result<MoveState, RtsException> {
+        {
+            let play_ground = self.map.borrow();
+            for identifier in &units {
+                Self::find_owned_unit(&play_ground, index, *identifier)?;
+            }
+        }
+        self.get_player(index)?.borrow_mut().set_group(name, units);
+        Ok(MoveState::AssignGroup)
     }
 
-    fn give_money(&self, player: InnerPlayer) -> Result<MoveState, RtsException> {
+    /// Units of the player picked by the selection, with where they stand and their speed
+    fn select_units(
+        &self,
+        index: PlayerIndex,
+        selection: &UnitSelection,
+    ) -> Result<Vec<(Identifier, Coordinate, i16)>, RtsException> {
+        let mut identifiers = match selection {
+            UnitSelection::Units(units) => units.clone(),
+            UnitSelection::Group(name) => self
+                .get_player(index)?
+                .borrow()
+                .get_group(name)
+                .cloned()
+                .ok_or_else(|| {
+                    RtsException::ExecuteActionException(format!(
+                        "Player {} has no group {}",
+                        index, name
+                    ))
+                })?,
+        };
+        identifiers.sort_unstable();
+        identifiers.dedup();
+        if identifiers.is_empty() {
+            return Err(RtsException::ExecuteActionException(
+                "No unit selected".to_string(),
+            ));
+        }
+        identifiers
+            .into_iter()
+            .map(|identifier| {
+                let from = self.orderable_unit(index, identifier)?;
+                let play_ground = self.map.borrow();
+                let speed = play_ground
+                    .find_cell(&identifier)
+                    .and_then(|cell| cell.borrow().get().map(|unit| *unit.get_speed()))
+                    .unwrap_or(0);
+                Ok((identifier, from, speed))
+            })
+            .collect()
+    }
+
+    /// Send the units to the coordinate in formation, walking at the pace of the slowest.
+    /// Members which can not reach their place in the formation head for the coordinate.
+    fn move_group(
+        &self,
+        index: PlayerIndex,
+        selection: UnitSelection,
+        coordinate: Coordinate,
+        formation: Formation,
+        attack: bool,
+    ) -> Result<MoveState, RtsException> {
+        if self.config.map.tile_of(&coordinate).is_none() {
+            return Err(RtsException::ExecuteActionException(format!(
+                "{} is out of the map",
+                coordinate
+            )));
+        }
+        let members = self.select_units(index, &selection)?;
+        let count = members.len() as i64;
+        let center = Coordinate::new(
+            (members
+                .iter()
+                .map(|(_, from, _)| from.x as i64)
+                .sum::<i64>()
+                / count) as i32,
+            (members
+                .iter()
+                .map(|(_, from, _)| from.y as i64)
+                .sum::<i64>()
+                / count) as i32,
+        );
+        let pace = members
+            .iter()
+            .map(|(_, _, speed)| *speed)
+            .min()
+            .unwrap_or(0);
+        let slots = formation.slots(members.len(), &center, &coordinate);
+        // Every path is found before any unit is given its order
+        let paths = members
+            .iter()
+            .zip(slots)
+            .map(|((identifier, from, _), slot)| {
+                find_path(&self.config.map, from, &slot)
+                    .map(|path| MoveOrder::new(slot, path))
+                    .or_else(|| {
+                        find_path(&self.config.map, from, &coordinate)
+                            .map(|path| MoveOrder::new(coordinate, path))
+                    })
+                    .map(|path| (*identifier, path.with_pace(pace)))
+                    .ok_or_else(|| {
+                        RtsException::ExecuteActionException(format!(
+                            "Unit {} can not reach {}",
+                            identifier, coordinate
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<(Identifier, MoveOrder)>, RtsException>>()?;
+        for (identifier, path) in paths {
+            let order = if attack {
+                Order::AttackMove(path)
+            } else {
+                Order::Move(path)
+            };
+            self.give_order(index, identifier, Some(order))?;
+        }
+        Ok(MoveState::GiveOrder)
+    }
+
+    /// Give the same order to every selected unit
+    fn order_group(
+        &self,
+        index: PlayerIndex,
+        selection: UnitSelection,
+        order: Option<Order>,
+    ) -> Result<MoveState, RtsException> {
+        for (identifier, _, _) in self.select_units(index, &selection)? {
+            self.give_order(index, identifier, order.clone())?;
+        }
+        Ok(MoveState::GiveOrder)
+    }
+
+    /// Room taken by the units on the map, but the excluded one
+    fn footprints(play_ground: &PlayGround<Unit>, excluded: Option<Identifier>) -> Vec<Footprint> {
+        play_ground
+            .get_cells()
+            .iter()
+            .filter_map(|cell| {
+                let holder = cell.borrow();
+                holder
+                    .get()
+                    .filter(|unit| Some(unit.get_identifier()) != excluded)
+                    .map(|unit| Footprint {
+                        coordinate: *holder.get_coordinate(),
+                        radius: *unit.get_radius(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Free place for a new unit of the player, around its barrack
+    /// or around the origin of the map when it has none
+    fn spawn_point(&self, index: PlayerIndex, radius: i32) -> Coordinate {
+        let map = &self.config.map;
+        let barrack = map
+            .get_buildings()
+            .iter()
+            .find(|site| site.kind == BuildingKind::Barrack && site.owner == index);
+        let (center, first_ring) = match barrack {
+            Some(site) => (site.coordinate, 1),
+            None => (Coordinate::default(), 0),
+        };
+        let others = Self::footprints(&self.map.borrow(), None);
+        find_free_spot(map, &center, first_ring, radius, &others).unwrap_or(center)
+    }
+
+    /// Carry out the standing orders of the units: those told to fight
+    /// and those without orders attack the enemies in range, the others walk
+    pub(crate) fn execute_orders(&self) -> Result<(), RtsException> {
+        let engaged = self.acquire_targets()?;
+        self.refresh_paths();
+        self.move_units(&engaged)
+    }
+
+    /// Turn patrols around at the end of their leg and send followers
+    /// after their target, followers of dead units stop
+    pub(crate) fn refresh_paths(&self) {
+        let units = self.placed_units();
+        let mut visibilities: BTreeMap<PlayerIndex, VisibilityMap> = BTreeMap::new();
+        let play_ground = self.map.borrow();
+        for cell in play_ground.get_cells() {
+            let mut holder = cell.borrow_mut();
+            let from = *holder.get_coordinate();
+            let unit = match holder.get_mut() {
+                Some(unit) => unit,
+                None => continue,
+            };
+            let refreshed = match unit.get_order() {
+                Some(Order::Patrol { from: start, path }) if path.is_done() => {
+                    let back = find_path(&self.config.map, &from, start)
+                        .map(|steps| MoveOrder::new(*start, steps))
+                        .unwrap_or_else(|| MoveOrder::new(*start, Vec::new()));
+                    Some(Order::Patrol {
+                        from: *path.get_destination(),
+                        path: back,
+                    })
+                }
+                Some(Order::Follow { target, .. }) => {
+                    let target = *target;
+                    // Followers only go where their owner believes the target is
+                    let believed = unit.get_owner().and_then(|owner| {
+                        let visibility = visibilities
+                            .entry(owner)
+                            .or_insert_with(|| self.visibility_of(owner, &units));
+                        self.believed_position(owner, target, &units, visibility)
+                    });
+                    match believed {
+                        Some(to) => {
+                            let path = Some(to)
+                                .filter(|to| !from.is_within(to, FOLLOW_DISTANCE))
+                                .and_then(|to| {
+                                    find_path(&self.config.map, &from, &to)
+                                        .map(|steps| MoveOrder::new(to, steps))
+                                });
+                            Some(Order::Follow { target, path })
+                        }
+                        None => {
+                            unit.set_order(None);
+                            continue;
+                        }
+                    }
+                }
+                _ => continue,
+            };
+            unit.set_order(refreshed);
+        }
+    }
+
+    /// Make units with a path walk as far as their speed allows this turn,
+    /// but the engaged ones which stay to fight.
+    /// Units walk one after the other and stop before running into another one.
+    pub(crate) fn move_units(&self, engaged: &[Identifier]) -> Result<(), RtsException> {
+        let mut moves = Vec::new();
+        {
+            let play_ground = self.map.borrow();
+            for cell in play_ground.get_cells() {
+                let identifier = match cell.borrow().get() {
+                    Some(unit)
+                        if unit
+                            .get_order()
+                            .as_ref()
+                            .and_then(Order::get_path)
+                            .is_some() =>
+                    {
+                        unit.get_identifier()
+                    }
+                    _ => continue,
+                };
+                if engaged.contains(&identifier) {
+                    continue;
+                }
+                let others = Self::footprints(&play_ground, Some(identifier));
+                let mut holder = cell.borrow_mut();
+                let from = *holder.get_coordinate();
+                let unit = match holder.get_mut() {
+                    Some(unit) => unit,
+                    None => continue,
+                };
+                let speed = unit.get_effective_speed();
+                let radius = *unit.get_radius();
+                let owner = *unit.get_owner();
+                let to = match unit.get_order_mut().and_then(Order::get_path_mut) {
+                    Some(path) => {
+                        let budget = path.limit_speed(speed) as i64 * TILE_SCALE as i64;
+                        let to = walk_clear(path, from, budget, radius, &others);
+                        // Someone stands on the destination, this is as close as it gets
+                        let taken = to == from
+                            && budget > 0
+                            && others
+                                .iter()
+                                .any(|other| other.overlaps(path.get_destination(), radius));
+                        if taken {
+                            path.stop();
+                        }
+                        to
+                    }
+                    None => continue,
+                };
+                if unit
+                    .get_order()
+                    .as_ref()
+                    .map(Order::is_done)
+                    .unwrap_or(false)
+                {
+                    unit.set_order(None);
+                }
+                holder.update(to);
+                if to != from {
+                    moves.push((identifier, owner, from, to));
+                }
+            }
+        }
+        for (identifier, owner, from, to) in moves {
+            self.notify_to(
+                GameEvent::UnitMoved {
+                    identifier,
+                    from,
+                    to,
+                },
+                self.audience(&[owner], &[from, to]),
+            );
+        }
+        Ok(())
+    }
+
+    fn give_money(&self, index: PlayerIndex) -> Result<MoveState, RtsException> {
+        let player = self.get_player(index)?;
         let mut player = player.borrow_mut();
-        Bank::give_money(&mut player).map(|_| {
-            println!("Successfuly give money to {}", player.get_name());
+        let before = *player.get_money();
+        Bank::give_money(&mut player, self.config.money_batch).map(|_| {
+            self.notify_to(
+                GameEvent::MoneyChanged {
+                    player: index,
+                    amount: player.get_money() - before,
+                    money: *player.get_money(),
+                },
+                vec![index],
+            );
             MoveState::GiveMoneyBatch
         })
     }
 
-    fn buy_unit(
-        &self,
-        unit_type: UnitType,
-        player: InnerPlayer,
-    ) -> Result<MoveState, RtsException> {
+    /// Pay for a technology, it is researched after its number of turns.
+    /// Every player knows what the others research.
+    fn research(&self, index: PlayerIndex, technology: String) -> Result<MoveState, RtsException> {
+        let player = self.get_player(index)?;
+        let mut player = player.borrow_mut();
+        let tree = &self.config.tech_tree;
+        let researched = tree.check_can_start(player.get_research(), &technology)?;
+        if *player.get_money() < researched.cost {
+            return Err(RtsException::ExecuteActionException(format!(
+                "Player {} does not have enough money to research {}",
+                player.get_name(),
+                technology
+            )));
+        }
+        player
+            .update_money(-researched.cost)
+            .ok_or_else(|| RtsException::UpdatePlayerException("Pay for research".to_string()))?;
+        player.get_research_mut().start(tree, &technology)?;
+
+        self.notify_to(
+            GameEvent::MoneyChanged {
+                player: index,
+                amount: -researched.cost,
+                money: *player.get_money(),
+            },
+            vec![index],
+        );
+        self.notify(GameEvent::ResearchStarted {
+            player: index,
+            technology,
+            turns: researched.turns,
+        });
+        Ok(MoveState::Research)
+    }
+
+    fn buy_unit(&self, unit_type: UnitType, index: PlayerIndex) -> Result<MoveState, RtsException> {
+        let player = self.get_player(index)?;
         let mut player = player.borrow_mut();
-        let unit = self.barrack.buy_unit(unit_type, &mut player)?;
-        println!("Player: {}, Unit: {}", player, unit);
+        let before = *player.get_money();
+        let mut unit = self.barrack.buy_unit(unit_type, &mut player)?;
+        unit.set_owner(index);
+
+        let coordinate = self.spawn_point(index, *unit.get_radius());
+        {
+            let play_ground_ptr = Rc::clone(&self.map);
+            let mut play_ground = play_ground_ptr.borrow_mut();
+            play_ground.update(unit.clone()); // Clone here should be ok, it will be the stored item
+            play_ground.update_cell(unit.get_identifier(), coordinate)?;
+        }
+
+        self.notify_to(
+            GameEvent::MoneyChanged {
+                player: index,
+                amount: player.get_money() - before,
+                money: *player.get_money(),
+            },
+            vec![index],
+        );
+        self.notify_to(
+            GameEvent::UnitSpawned {
+                player: index,
+                identifier: unit.get_identifier(),
+                unit_type,
+                coordinate,
+            },
+            self.audience(&[Some(index)], &[coordinate]),
+        );
         Ok(MoveState::BuyUnit(unit))
     }
 }
 
+/// Games and observers shared by the tests of the engine
 #[cfg(test)]
-mod tests_play_ground {
+pub(crate) mod test_game {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     use crate::components::game::Game;
+    use crate::components::game_config::GameConfig;
+    use crate::components::map::Map;
+    use crate::components::play_ground::{Identifier, PlayGroundObserver};
     use crate::components::turn_strategy::TurnStrategy;
-    use crate::entity::game_actions::{Action, MoveState};
-    use crate::entity::player::Player;
-    use crate::entity::unit::UnitType;
+    use crate::entity::game_event::Notification;
+    use crate::entity::player::{Player, PlayerIndex};
+    use crate::entity::unit::Unit;
 
     use super::GameStateObserver;
 
-    struct TestClientGameState();
+    pub struct TestClientGameState();
     impl GameStateObserver for TestClientGameState {
-        fn update(&self, _m: &MoveState) {
-            println!("client has been updated !");
+        fn update(&self, _n: &Notification) {}
+    }
+
+    pub struct RecordingClientGameState(pub Rc<RefCell<Vec<Notification>>>);
+    impl GameStateObserver for RecordingClientGameState {
+        fn update(&self, n: &Notification) {
+            self.0.borrow_mut().push(n.clone());
+        }
+    }
+
+    pub struct ViewpointClientGameState(pub PlayerIndex, pub Rc<RefCell<Vec<Notification>>>);
+    impl GameStateObserver for ViewpointClientGameState {
+        fn update(&self, n: &Notification) {
+            self.1.borrow_mut().push(n.clone());
+        }
+
+        fn viewpoint(&self) -> Option<PlayerIndex> {
+            Some(self.0)
+        }
+    }
+
+    pub fn rich_player(name: &str) -> Player<TurnStrategy> {
+        let mut player = Player::new(name.to_string(), TurnStrategy::AI);
+        player.update_money(100);
+        player
+    }
+
+    /// Tigran against Emma on an empty map, without barracks units spawn next to each other
+    pub fn duel<S: GameStateObserver>(observer: S) -> Game<S> {
+        duel_on(Map::new(24, 12), observer)
+    }
+
+    pub fn duel_on<S: GameStateObserver>(map: Map, observer: S) -> Game<S> {
+        let config = GameConfig {
+            map,
+            ..GameConfig::default()
+        };
+        duel_with(config, observer)
+    }
+
+    pub fn duel_with<S: GameStateObserver>(config: GameConfig, observer: S) -> Game<S> {
+        Game::with_config(
+            vec![rich_player("Tigran"), rich_player("Emma")],
+            vec![observer],
+            config,
+        )
+    }
+
+    /// Let units walk until they reached their destination, without fighting
+    pub fn walk<S: GameStateObserver>(game: &Game<S>) {
+        for _ in 0..20 {
+            game.refresh_paths();
+            game.move_units(&[]).unwrap();
         }
     }
 
+    /// Change a unit on the map, as no action could
+    pub fn update_unit<S: GameStateObserver>(
+        game: &Game<S>,
+        identifier: Identifier,
+        update: impl FnOnce(&mut Unit),
+    ) {
+        let cell = game.map.borrow().find_cell(&identifier).unwrap();
+        update(cell.borrow_mut().get_mut().unwrap());
+    }
+
+    /// Put a unit on the map, where it is said to stand
+    pub fn place_unit<S: GameStateObserver>(game: &Game<S>, unit: Unit) {
+        game.map.borrow_mut().update(unit);
+    }
+}
+
+#[cfg(test)]
+mod tests_play_ground {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::formation::Formation;
+    use crate::components::game::Game;
+    use crate::components::game_config::GameConfig;
+    use crate::components::map::{CapturePoint, Map};
+    use crate::components::play_ground::{Coordinate, HasIdentifier};
+    use crate::components::scenario::{Condition, Scenario, Trigger};
+    use crate::components::turn_strategy::TurnStrategy;
+    use crate::entity::game_actions::{Action, MoveState, UnitSelection};
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::player::Player;
+    use crate::entity::unit::UnitType;
+
+    use super::test_game::{
+        duel, duel_on, duel_with, rich_player, RecordingClientGameState, TestClientGameState,
+    };
+
     #[test]
     pub fn should_play_with_ai() {
         let mut tigran = Player::new("Tigran".to_string(), TurnStrategy::AI);
@@ -236,4 +1637,270 @@ mod tests_play_ground {
         let res = game.play(1, Action::BuyUnit(UnitType::Classic));
         assert!(res.is_err());
     }
+
+    #[test]
+    pub fn should_notify_observers_once() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::new(
+            vec![rich_player("Tigran")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+        );
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.update_observers().unwrap();
+        game.update_observers().unwrap();
+
+        let received = received.borrow();
+        let spawns = received
+            .iter()
+            .filter(|n| matches!(n.get_event(), GameEvent::UnitSpawned { .. }))
+            .count();
+        assert_eq!(1, spawns);
+        assert!(received
+            .windows(2)
+            .all(|w| w[0].get_sequence() < w[1].get_sequence()));
+    }
+
+    #[test]
+    pub fn should_reject_moving_enemy_unit() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::new(
+            vec![rich_player("Tigran"), rich_player("Emma")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+        );
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        assert!(game
+            .play(1, Action::MoveUnit(1, Coordinate::from_tiles(2, 2)))
+            .is_err());
+        game.update_observers().unwrap();
+
+        assert!(matches!(
+            received.borrow().last().unwrap().get_event(),
+            GameEvent::ActionRejected { player: 1, .. }
+        ));
+        // Its owner may move it, like any other order
+        assert!(matches!(
+            game.execute_action(0, Action::MoveUnit(1, Coordinate::from_tiles(2, 2))),
+            Ok(MoveState::GiveOrder)
+        ));
+    }
+
+    #[test]
+    pub fn should_reject_orders_to_the_limits_of_coordinates() {
+        let game = duel(TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        for far in [
+            Coordinate::new(i32::MAX, i32::MAX),
+            Coordinate::new(i32::MIN, 0),
+        ] {
+            assert!(game.play(0, Action::MoveUnit(1, far)).is_err());
+            assert!(game.play(0, Action::AttackMove(1, far)).is_err());
+            assert!(game.play(0, Action::Patrol(1, far)).is_err());
+            assert!(game
+                .play(
+                    0,
+                    Action::MoveGroup(UnitSelection::Units(vec![1]), far, Formation::Line)
+                )
+                .is_err());
+        }
+    }
+
+    #[test]
+    pub fn should_play_replay_again() {
+        let tigran = Player::new(
+            "Tigran".to_string(),
+            TurnStrategy::scripted(vec![
+                Action::BuyUnit(UnitType::Classic),
+                Action::MoveUnit(1, Coordinate::from_tiles(3, 2)),
+                Action::UseSkill(1, 0, 2),
+            ]),
+        );
+        let emma = Player::new(
+            "Emma".to_string(),
+            TurnStrategy::scripted(vec![
+                Action::BuyUnit(UnitType::Classic),
+                Action::Wait,
+                Action::EndGame,
+            ]),
+        );
+        let game = Game::new(vec![tigran, emma], vec![TestClientGameState()]);
+        while !game.play_turn().unwrap() {}
+
+        let replay = game.get_replay();
+        assert_eq!(3, replay.turns.len());
+        let replayed = Game::from_replay(&replay, vec![TestClientGameState()]);
+        while !replayed.play_turn().unwrap() {}
+
+        assert_eq!(game.snapshot(), replayed.snapshot());
+    }
+
+    #[test]
+    pub fn should_draw_same_numbers_with_same_seed() {
+        let config = GameConfig {
+            seed: 1234,
+            ..GameConfig::default()
+        };
+        let first = Game::with_config(
+            vec![rich_player("Tigran")],
+            vec![TestClientGameState()],
+            config.clone(),
+        );
+        let second = Game::with_config(
+            vec![rich_player("Tigran")],
+            vec![TestClientGameState()],
+            config,
+        );
+
+        let draw = |game: &Game<TestClientGameState>| {
+            let rng = game.get_rng();
+            let mut rng = rng.borrow_mut();
+            (0..5).map(|_| rng.range(0, 1000)).collect::<Vec<i64>>()
+        };
+        assert_eq!(draw(&first), draw(&second));
+        assert_eq!(1234, first.get_info().config.seed);
+    }
+
+    #[test]
+    pub fn should_research_upgrades() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::new(
+            vec![rich_player("Tigran"), rich_player("Emma")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+        );
+
+        assert!(game.play(0, Action::BuyUnit(UnitType::Mage)).is_err());
+        assert!(game
+            .play(0, Action::Research("heavy_plating".to_string()))
+            .is_err());
+        game.play(0, Action::Research("reinforced_armor".to_string()))
+            .unwrap();
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        assert!(game
+            .play(0, Action::Research("trade_routes".to_string()))
+            .is_err());
+
+        game.begin_turn().unwrap();
+        game.begin_turn().unwrap();
+        let armor = {
+            let play_ground = game.map.borrow();
+            let cell = play_ground.find_cell(&1).unwrap();
+            let armor = *cell.borrow().get().unwrap().get_armor();
+            armor
+        };
+        assert_eq!(6, armor);
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        let observation = game.observe(0).unwrap();
+        assert_eq!(&6, observation.units[1].unit.get_armor());
+
+        // The enemy knows it too
+        let research = &game.observe(1).unwrap().research[0];
+        assert_eq!(&["reinforced_armor".to_string()], research.get_completed());
+        game.update_observers().unwrap();
+        assert!(received.borrow().iter().any(|n| matches!(
+            n.get_event(),
+            GameEvent::ResearchCompleted { player: 0, .. }
+        )));
+    }
+
+    #[test]
+    pub fn should_fight_along_allies() {
+        let config = GameConfig {
+            map: Map::new(24, 12),
+            teams: vec![vec![0, 2], vec![1, 3]],
+            ..GameConfig::default()
+        };
+        let game = Game::with_config(
+            vec![
+                rich_player("Tigran"),
+                rich_player("Emma"),
+                rich_player("Lucie"),
+                rich_player("Hugo"),
+            ],
+            vec![TestClientGameState()],
+            config,
+        );
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(2, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        assert!(game.play(0, Action::UseSkill(1, 0, 2)).is_err());
+
+        let observation = game.observe(2).unwrap();
+        assert_eq!(1, observation.allies[0].unit.get_identifier());
+        assert_eq!(3, observation.enemies[0].unit.get_identifier());
+        // Allies see what the others see
+        assert!(observation
+            .visibility
+            .is_visible(&Coordinate::from_tiles(0, 6)));
+
+        game.execute_orders().unwrap();
+        let snapshot = game.snapshot();
+        assert_eq!(&20, snapshot.units[1].unit.get_health());
+        assert_eq!(&10, snapshot.units[2].unit.get_health());
+
+        let result = game.match_result();
+        assert_eq!(vec![0, 1, 0, 1], result.teams);
+        assert_eq!(None, result.winning_team);
+    }
+
+    #[test]
+    pub fn should_hash_capture_owners() {
+        let map = Map::new(24, 12).with_capture_points(vec![CapturePoint {
+            coordinate: Coordinate::from_tiles(3, 3),
+            radius: 1,
+            income: 10,
+            score: 3,
+        }]);
+        let game = duel_on(map, TestClientGameState());
+
+        let hash = game.state_hash();
+        game.captures.borrow_mut()[0] = Some(1);
+        assert_ne!(hash, game.state_hash());
+    }
+
+    #[test]
+    pub fn should_hash_fired_triggers() {
+        let config = GameConfig {
+            map: Map::new(24, 12),
+            scenario: Some(Scenario {
+                triggers: vec![Trigger {
+                    condition: Condition::Turn { turn: 1 },
+                    effects: Vec::new(),
+                }],
+                ..Scenario::default()
+            }),
+            ..GameConfig::default()
+        };
+        let game = duel_with(config, TestClientGameState());
+
+        let hash = game.state_hash();
+        game.fired_triggers.borrow_mut()[0] = true;
+        assert_ne!(hash, game.state_hash());
+    }
+
+    #[test]
+    pub fn should_spawn_around_barrack() {
+        let game = Game::new(
+            vec![rich_player("Tigran"), rich_player("Emma")],
+            vec![TestClientGameState()],
+        );
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+
+        let snapshot = game.snapshot();
+        let coordinates: Vec<Coordinate> = snapshot
+            .units
+            .iter()
+            .map(|placed| placed.coordinate)
+            .collect();
+        assert_eq!(
+            vec![
+                Coordinate::from_tiles(0, 0),
+                Coordinate::from_tiles(1, 0),
+                Coordinate::from_tiles(21, 9)
+            ],
+            coordinates
+        );
+    }
 }
@@ -0,0 +1,677 @@
+use std::collections::BTreeMap;
+
+use crate::components::game::{Game, GameStateObserver};
+use crate::components::match_result::ScoreSource;
+use crate::components::play_ground::{Coordinate, HasIdentifier, Identifier};
+use crate::components::snapshot::{AttackSnapshot, UnitSnapshot};
+use crate::components::visibility::VisibilityMap;
+use crate::entity::game_actions::MoveState;
+use crate::entity::game_event::GameEvent;
+use crate::entity::order::Order;
+use crate::entity::player::PlayerIndex;
+use crate::entity::skill::{Skill, SkillKind, SkillShape};
+use crate::entity::status_effect::StatusEffect;
+use crate::entity::unit::Unit;
+use crate::exceptions::RtsException;
+
+/// Skills reaching further than this many tiles need a clear line of sight
+const MELEE_RANGE: i16 = 1;
+/// Damage gained per level of elevation above the target, lost per level below
+const ELEVATION_BONUS: i16 = 2;
+
+/// Where a skill is cast from and aimed at
+struct Aim {
+    from: Coordinate,
+    point: Coordinate,
+    /// Of the caster, in tiles
+    range: i16,
+}
+
+impl<StateClient> Game<StateClient>
+where
+    StateClient: GameStateObserver,
+{
+    /// Remove a dead unit from the map and credit its killer
+    pub(super) fn kill_unit(
+        &self,
+        unit: &Unit,
+        killer: Identifier,
+        killer_owner: Option<PlayerIndex>,
+        audience: Vec<PlayerIndex>,
+    ) -> Result<(), RtsException> {
+        let identifier = unit.get_identifier();
+        self.map.borrow_mut().remove_unit(&identifier);
+        if let Some(owner) = unit.get_owner() {
+            self.get_player(*owner)?
+                .borrow_mut()
+                .forget_unit(identifier);
+        }
+        let enemy_killer = killer_owner.filter(|owner| !self.is_ally(*owner, unit.get_owner()));
+        if let Some(owner) = enemy_killer {
+            let cost = self
+                .config
+                .catalogue
+                .get_cost(unit.get_unit_type())
+                .unwrap_or(0);
+            let source = match unit.get_owner() {
+                Some(_) => ScoreSource::Kill,
+                None => ScoreSource::Creep,
+            };
+            self.get_player(owner)?
+                .borrow_mut()
+                .score_points(source, cost as i32);
+        }
+        self.notify_to(
+            GameEvent::UnitDied {
+                identifier,
+                killed_by: killer,
+            },
+            audience,
+        );
+        if enemy_killer.is_some() {
+            let experience = self
+                .config
+                .catalogue
+                .get_kill_experience(unit.get_unit_type());
+            self.reward_unit(killer, experience)?;
+        }
+        Ok(())
+    }
+
+    /// Give experience to a unit which fought enemies, it may rank up.
+    /// Nothing happens if the unit is dead.
+    fn reward_unit(&self, identifier: Identifier, experience: u32) -> Result<(), RtsException> {
+        if experience == 0 {
+            return Ok(());
+        }
+        let promoted = {
+            let play_ground = self.map.borrow();
+            let cell = match play_ground.find_cell(&identifier) {
+                Some(cell) => cell,
+                None => return Ok(()),
+            };
+            let mut holder = cell.borrow_mut();
+            let coordinate = *holder.get_coordinate();
+            match holder.get_mut() {
+                Some(unit) => {
+                    unit.gain_experience(experience);
+                    let ranks = self.config.catalogue.promote(unit);
+                    Some((unit.clone(), coordinate)).filter(|_| ranks > 0)
+                }
+                None => None,
+            }
+        };
+        if let Some((unit, coordinate)) = promoted {
+            self.notify_to(
+                GameEvent::UnitPromoted {
+                    identifier,
+                    rank: *unit.get_rank(),
+                    experience: *unit.get_experience(),
+                },
+                self.audience(&[*unit.get_owner()], &[coordinate]),
+            );
+        }
+        Ok(())
+    }
+
+    /// Make units fight by themselves: those without orders and those told to.
+    /// Each player looks around once, the units fight with what it saw.
+    /// Returns the units which stay in place to fight this turn.
+    pub(super) fn acquire_targets(&self) -> Result<Vec<Identifier>, RtsException> {
+        let units = self.placed_units();
+        let mut visibilities: BTreeMap<PlayerIndex, VisibilityMap> = BTreeMap::new();
+        let mut engaged = Vec::new();
+        for placed in units.iter() {
+            let engages = placed
+                .unit
+                .get_order()
+                .as_ref()
+                .map(Order::engages)
+                .unwrap_or(true);
+            if let (true, Some(owner)) = (engages, *placed.unit.get_owner()) {
+                let visibility = visibilities
+                    .entry(owner)
+                    .or_insert_with(|| self.visibility_of(owner, &units));
+                if self.engage(owner, placed, &units, visibility)? {
+                    engaged.push(placed.unit.get_identifier());
+                }
+            }
+        }
+        Ok(engaged)
+    }
+
+    /// Attack the enemy the player sees in range of the unit, picked by the policy of its type.
+    /// Units are taken as they stood when the fight began, those killed since are left aside.
+    /// Returns true if there is one, even when the unit has to wait for its skills.
+    fn engage(
+        &self,
+        index: PlayerIndex,
+        placed: &UnitSnapshot,
+        units: &[UnitSnapshot],
+        visibility: &VisibilityMap,
+    ) -> Result<bool, RtsException> {
+        let identifier = placed.unit.get_identifier();
+        // Earlier fights may have stunned or killed the unit
+        let unit = match self.standing_unit(identifier) {
+            Some(unit) if !unit.is_stunned() => unit,
+            _ => return Ok(false),
+        };
+        let enemies = units
+            .iter()
+            .filter(|other| !self.is_ally(index, other.unit.get_owner()))
+            .filter(|other| visibility.is_visible(&other.coordinate))
+            .filter(|other| {
+                let play_ground = self.map.borrow();
+                play_ground
+                    .find_cell(&other.unit.get_identifier())
+                    .is_some()
+            });
+        let target = match self.pick_target(placed, enemies) {
+            Some(target) => target,
+            None => return Ok(false),
+        };
+        if let Some(skill) = Self::ready_attack(&unit) {
+            self.cast_skill(
+                index,
+                identifier,
+                skill,
+                target.coordinate,
+                Some(target.unit.get_identifier()),
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Current state of the unit, if it is still on the map
+    fn standing_unit(&self, identifier: Identifier) -> Option<Unit> {
+        let play_ground = self.map.borrow();
+        let cell = play_ground.find_cell(&identifier)?;
+        let unit = cell.borrow().get().cloned();
+        unit
+    }
+
+    /// Enemy in range of the unit, picked by the policy of its type
+    fn pick_target<'a>(
+        &self,
+        placed: &UnitSnapshot,
+        enemies: impl Iterator<Item = &'a UnitSnapshot>,
+    ) -> Option<&'a UnitSnapshot> {
+        let range = *placed.unit.get_range();
+        let policy = self
+            .config
+            .catalogue
+            .get_targeting(placed.unit.get_unit_type());
+        enemies
+            .filter(|other| placed.coordinate.is_within(&other.coordinate, range))
+            .filter(|other| {
+                range <= MELEE_RANGE
+                    || self
+                        .config
+                        .map
+                        .has_line_of_sight(&placed.coordinate, &other.coordinate)
+            })
+            .min_by_key(|other| {
+                (
+                    policy.priority(&other.unit),
+                    placed.coordinate.squared_distance(&other.coordinate),
+                    other.unit.get_identifier(),
+                )
+            })
+    }
+
+    /// First damage skill the unit can cast right now
+    fn ready_attack(unit: &Unit) -> Option<usize> {
+        let mana = *unit.get_mana();
+        unit.get_skills().iter().position(|skill| {
+            skill.get_kind() == &SkillKind::Damage
+                && skill.is_ready()
+                && mana >= *skill.get_mana_cost() as i16
+        })
+    }
+
+    /// Built-in strategy of neutral units: they guard their camp
+    /// and attack the units they see in range, never moving
+    pub(crate) fn command_creeps(&self) -> Result<(), RtsException> {
+        let creeps: Vec<Identifier> = self
+            .placed_units()
+            .iter()
+            .filter(|placed| placed.unit.get_owner().is_none())
+            .map(|placed| placed.unit.get_identifier())
+            .collect();
+        for identifier in creeps {
+            self.creep_attack(identifier)?;
+        }
+        Ok(())
+    }
+
+    fn creep_attack(&self, identifier: Identifier) -> Result<(), RtsException> {
+        let units = self.placed_units();
+        let placed = match units
+            .iter()
+            .find(|placed| placed.unit.get_identifier() == identifier)
+        {
+            Some(placed) if !placed.unit.is_stunned() => placed,
+            _ => return Ok(()),
+        };
+        let vision = self
+            .config
+            .catalogue
+            .get_vision(placed.unit.get_unit_type());
+        let players_units = units.iter().filter(|other| {
+            other.unit.get_owner().is_some()
+                && placed.coordinate.is_within(&other.coordinate, vision)
+        });
+        let (target, skill_index) = match (
+            self.pick_target(placed, players_units),
+            Self::ready_attack(&placed.unit),
+        ) {
+            (Some(target), Some(skill_index)) => (target, skill_index),
+            _ => return Ok(()),
+        };
+        let skill = placed.unit.get_skills()[skill_index].clone();
+        if let Some(cell) = self.map.borrow().find_cell(&identifier) {
+            if let Some(creep) = cell.borrow_mut().get_mut() {
+                creep.update_mana(-(*skill.get_mana_cost() as i16))?;
+                if let Some(creep_skill) = creep.get_skill_mut(skill_index) {
+                    creep_skill.trigger();
+                }
+            }
+        }
+        let effect = skill
+            .get_effect()
+            .map(|effect| effect.from_source(identifier, None));
+        self.hit_unit(
+            None,
+            identifier,
+            placed.coordinate,
+            &skill,
+            effect,
+            target.unit.get_identifier(),
+        )
+    }
+
+    /// Cast a skill on a unit, area skills are centered on it
+    pub(super) fn use_skill(
+        &self,
+        index: PlayerIndex,
+        caster: Identifier,
+        skill: usize,
+        target: Identifier,
+    ) -> Result<MoveState, RtsException> {
+        // Hidden units can not be told from missing ones
+        let units = self.placed_units();
+        let visibility = self.visibility_of(index, &units);
+        let point = units
+            .iter()
+            .find(|placed| placed.unit.get_identifier() == target)
+            .map(|placed| placed.coordinate)
+            .filter(|coordinate| visibility.is_visible(coordinate))
+            .ok_or_else(|| {
+                RtsException::ExecuteActionException(format!("Failed to find unit {}", target))
+            })?;
+        self.cast_skill(index, caster, skill, point, Some(target))
+    }
+
+    /// Cast a skill on a point of the map
+    pub(super) fn use_skill_at(
+        &self,
+        index: PlayerIndex,
+        caster: Identifier,
+        skill: usize,
+        point: Coordinate,
+    ) -> Result<MoveState, RtsException> {
+        self.cast_skill(index, caster, skill, point, None)
+    }
+
+    fn cast_skill(
+        &self,
+        index: PlayerIndex,
+        caster: Identifier,
+        skill_index: usize,
+        point: Coordinate,
+        target: Option<Identifier>,
+    ) -> Result<MoveState, RtsException> {
+        // Check everything before the caster pays for the skill
+        let (from, range, skill) = {
+            let play_ground = self.map.borrow();
+            let caster_cell = Self::find_owned_unit(&play_ground, index, caster)?;
+            let caster_holder = caster_cell.borrow();
+            let caster_unit = caster_holder.get().ok_or_else(|| {
+                RtsException::ExecuteActionException(format!("Unit {} is gone", caster))
+            })?;
+            if caster_unit.is_stunned() {
+                return Err(RtsException::ExecuteActionException(format!(
+                    "Unit {} is stunned",
+                    caster
+                )));
+            }
+            let skill = caster_unit
+                .get_skills()
+                .get(skill_index)
+                .cloned()
+                .ok_or_else(|| {
+                    RtsException::ExecuteActionException(format!(
+                        "Unit {} has no skill {}",
+                        caster, skill_index
+                    ))
+                })?;
+            if !skill.is_ready() {
+                return Err(RtsException::ExecuteActionException(format!(
+                    "Skill {} of unit {} is cooling down",
+                    skill_index, caster
+                )));
+            }
+            if *caster_unit.get_mana() < *skill.get_mana_cost() as i16 {
+                return Err(RtsException::ExecuteActionException(format!(
+                    "Unit {} does not have enough mana",
+                    caster
+                )));
+            }
+            let from = *caster_holder.get_coordinate();
+            let range = *caster_unit.get_range();
+            if !from.is_within(&point, range) {
+                // The point is left out, it would tell where a hidden target stands
+                return Err(RtsException::ExecuteActionException(format!(
+                    "Target is out of range of unit {}",
+                    caster
+                )));
+            }
+            if range > MELEE_RANGE && !self.config.map.has_line_of_sight(&from, &point) {
+                return Err(RtsException::ExecuteActionException(format!(
+                    "Unit {} has no line of sight on its target",
+                    caster
+                )));
+            }
+            (from, range, skill)
+        };
+        let aim = Aim { from, point, range };
+        let targets = self.skill_targets(index, caster, &skill, &aim, target)?;
+
+        {
+            let play_ground = self.map.borrow();
+            let caster_cell = Self::find_owned_unit(&play_ground, index, caster)?;
+            let mut caster_holder = caster_cell.borrow_mut();
+            if let Some(caster_unit) = caster_holder.get_mut() {
+                caster_unit.update_mana(-(*skill.get_mana_cost() as i16))?;
+                if let Some(caster_skill) = caster_unit.get_skill_mut(skill_index) {
+                    caster_skill.trigger();
+                }
+            }
+        }
+
+        let effect = skill
+            .get_effect()
+            .map(|effect| effect.from_source(caster, Some(index)));
+        for target in targets {
+            self.hit_unit(Some(index), caster, from, &skill, effect, target)?;
+        }
+        Ok(MoveState::UseSkill)
+    }
+
+    /// Units hit by the skill: the target of single target skills,
+    /// enemies in the area for damage and allies in the area for heals
+    fn skill_targets(
+        &self,
+        index: PlayerIndex,
+        caster: Identifier,
+        skill: &Skill,
+        aim: &Aim,
+        target: Option<Identifier>,
+    ) -> Result<Vec<Identifier>, RtsException> {
+        match (skill.get_shape(), target) {
+            (SkillShape::Single, None) => Err(RtsException::ExecuteActionException(format!(
+                "Skills of unit {} with a single target need a unit",
+                caster
+            ))),
+            (SkillShape::Single, Some(target)) => {
+                let target_owner = self
+                    .map
+                    .borrow()
+                    .find_cell(&target)
+                    .and_then(|cell| cell.borrow().get().and_then(|unit| *unit.get_owner()));
+                match skill.get_kind() {
+                    _ if self.may_hit(index, skill, &target_owner) => Ok(vec![target]),
+                    SkillKind::Damage => Err(RtsException::ExecuteActionException(format!(
+                        "Unit {} can not attack the ally unit {}",
+                        caster, target
+                    ))),
+                    SkillKind::Heal => Err(RtsException::ExecuteActionException(format!(
+                        "Unit {} can not heal the enemy unit {}",
+                        caster, target
+                    ))),
+                }
+            }
+            (shape, _) => Ok(self
+                .placed_units()
+                .into_iter()
+                .filter(|placed| self.may_hit(index, skill, placed.unit.get_owner()))
+                .filter(|placed| shape.covers(&aim.from, &aim.point, aim.range, &placed.coordinate))
+                .map(|placed| placed.unit.get_identifier())
+                .collect()),
+        }
+    }
+
+    /// Damage only hits enemies and heals only allies, the units of the player included
+    fn may_hit(&self, index: PlayerIndex, skill: &Skill, owner: &Option<PlayerIndex>) -> bool {
+        let is_ally = self.is_ally(index, owner);
+        match skill.get_kind() {
+            SkillKind::Damage => !is_ally,
+            SkillKind::Heal => is_ally,
+        }
+    }
+
+    /// Apply the skill of the caster to one of the units it hits
+    fn hit_unit(
+        &self,
+        owner: Option<PlayerIndex>,
+        caster: Identifier,
+        from: Coordinate,
+        skill: &Skill,
+        effect: Option<StatusEffect>,
+        target: Identifier,
+    ) -> Result<(), RtsException> {
+        let map = &self.config.map;
+        let (amount, target_unit, effect, to) = {
+            let play_ground = self.map.borrow();
+            let target_cell = play_ground.find_cell(&target).ok_or_else(|| {
+                RtsException::ExecuteActionException(format!("Failed to find unit {}", target))
+            })?;
+            let mut target_holder = target_cell.borrow_mut();
+            let to = *target_holder.get_coordinate();
+            let target_unit = target_holder.get_mut().ok_or_else(|| {
+                RtsException::ExecuteActionException(format!("Unit {} is gone", target))
+            })?;
+            let amount = match skill.get_kind() {
+                SkillKind::Damage => {
+                    let elevation = map.terrain_at(&from).get_elevation() as i16
+                        - map.terrain_at(&to).get_elevation() as i16;
+                    let damage = self.config.catalogue.get_effectiveness().apply(
+                        skill
+                            .get_damage()
+                            .saturating_add(elevation * ELEVATION_BONUS),
+                        skill.get_damage_type(),
+                        target_unit.get_armor_class(),
+                    );
+                    target_unit
+                        .take_damage(damage.saturating_sub(target_unit.get_effective_armor()))?
+                }
+                SkillKind::Heal => {
+                    let before = *target_unit.get_health();
+                    target_unit.update_health((*skill.get_damage()).max(0))?;
+                    target_unit.get_health() - before
+                }
+            };
+            let effect = effect.filter(|_| target_unit.is_alive());
+            if let Some(effect) = effect {
+                target_unit.apply_effect(effect);
+            }
+            (amount, target_unit.clone(), effect, to)
+        };
+
+        // Computed before the target may die, to know who saw the fight
+        let audience = self.audience(&[owner, *target_unit.get_owner()], &[from, to]);
+        match skill.get_kind() {
+            SkillKind::Damage => {
+                self.attacks.borrow_mut().push(AttackSnapshot {
+                    attacker: caster,
+                    target,
+                    from,
+                    to,
+                });
+                self.notify_to(
+                    GameEvent::UnitAttacked {
+                        attacker: caster,
+                        target,
+                        damage: amount,
+                        remaining_health: *target_unit.get_health(),
+                    },
+                    audience.clone(),
+                );
+                self.reward_unit(caster, self.config.catalogue.get_damage_experience(amount))?;
+            }
+            SkillKind::Heal => self.notify_to(
+                GameEvent::UnitHealed {
+                    healer: caster,
+                    target,
+                    amount,
+                    health: *target_unit.get_health(),
+                },
+                audience.clone(),
+            ),
+        }
+        if let Some(effect) = effect {
+            self.notify_to(
+                GameEvent::EffectApplied {
+                    identifier: target,
+                    effect,
+                },
+                audience.clone(),
+            );
+        }
+        if !target_unit.is_alive() {
+            self.kill_unit(&target_unit, caster, owner, audience)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_combat {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{
+        duel, duel_on, update_unit, RecordingClientGameState, TestClientGameState,
+    };
+    use crate::components::map::{Map, Terrain};
+    use crate::entity::game_actions::Action;
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::skill::Skill;
+    use crate::entity::status_effect::{EffectKind, StatusEffect};
+    use crate::entity::unit::UnitType;
+
+    #[test]
+    pub fn should_kill_unit_with_skill() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = duel(RecordingClientGameState(Rc::clone(&received)));
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        for _ in 0..4 {
+            game.play(0, Action::UseSkill(1, 0, 2)).unwrap();
+        }
+        game.update_observers().unwrap();
+
+        assert!(matches!(
+            received.borrow().last().unwrap().get_event(),
+            GameEvent::UnitDied {
+                identifier: 2,
+                killed_by: 1
+            }
+        ));
+        assert_eq!(1, game.map.borrow().get_cells().len());
+        // The damage dealt was enough to rank up once
+        assert!(received.borrow().iter().any(|n| matches!(
+            n.get_event(),
+            GameEvent::UnitPromoted {
+                identifier: 1,
+                rank: 1,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    pub fn should_resist_damage_by_armor_class() {
+        let game = duel(TestClientGameState());
+
+        game.play(0, Action::BuyUnit(UnitType::Archer)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Knight)).unwrap();
+        assert_eq!(&65, game.get_players()[1].borrow().get_money());
+
+        // Arrows bounce on heavy armor, swords cut through light one
+        game.play(0, Action::UseSkill(1, 0, 2)).unwrap();
+        game.play(1, Action::UseSkill(2, 0, 1)).unwrap();
+        let health = |identifier| {
+            let play_ground = game.map.borrow();
+            let cell = play_ground.find_cell(&identifier).unwrap();
+            let health = *cell.borrow().get().unwrap().get_health();
+            health
+        };
+        assert_eq!(35, health(2));
+        assert_eq!(5, health(1));
+    }
+
+    #[test]
+    pub fn should_saturate_damage_from_high_ground() {
+        let mut map = Map::new(24, 12);
+        map.set_terrain(0, 0, Terrain::HighGround);
+        let game = duel_on(map, TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        update_unit(&game, 1, |unit| {
+            *unit.get_skill_mut(0).unwrap() = Skill::from(0, i16::MAX, 0)
+        });
+
+        game.play(0, Action::UseSkill(1, 0, 2)).unwrap();
+        assert_eq!(1, game.map.borrow().get_cells().len());
+    }
+
+    #[test]
+    pub fn should_acquire_targets_when_idle() {
+        let game = duel(TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Archer)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        update_unit(&game, 2, |unit| {
+            unit.apply_effect(StatusEffect::new(EffectKind::Stun, 1))
+        });
+        update_unit(&game, 3, |unit| unit.update_health(-5).unwrap());
+
+        game.execute_orders().unwrap();
+        let snapshot = game.snapshot();
+        // Both enemies are as close, archers go for the weakest
+        assert_eq!(&20, snapshot.units[1].unit.get_health());
+        assert_eq!(&8, snapshot.units[2].unit.get_health());
+        // Enemies without orders fight back
+        assert_eq!(&7, snapshot.units[0].unit.get_health());
+        assert!(game.observe(0).unwrap().orders.is_empty());
+    }
+
+    #[test]
+    pub fn should_leave_aside_enemies_killed_in_the_turn() {
+        let game = duel(TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        update_unit(&game, 3, |unit| unit.update_health(-19).unwrap());
+
+        game.execute_orders().unwrap();
+        let snapshot = game.snapshot();
+        assert_eq!(2, snapshot.units.len());
+        // Only the first unit struck, the second found nobody left to fight
+        assert!(*snapshot.units[0].unit.get_experience() > 0);
+        assert_eq!(&0, snapshot.units[1].unit.get_experience());
+    }
+}
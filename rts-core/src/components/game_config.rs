@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::map::Map;
+use crate::components::scenario::Scenario;
+use crate::components::tech_tree::TechTree;
+use crate::components::unit_catalogue::UnitCatalogue;
+use crate::entity::player::{PlayerIndex, TeamId};
+
+const TURN_DURATION_IN_SECONDS: u64 = 10;
+const NEW_MONEY_BATCH: i32 = 100;
+const DEFAULT_SEED: u64 = 0;
+
+/// Rules of a game, shared by the engine and whoever records the match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub turn_duration_in_seconds: u64,
+    /// Money given to each player at the beginning of a turn
+    pub money_batch: i32,
+    pub map: Map,
+    /// Seed of the game random numbers, the same seed replays the same game
+    pub seed: u64,
+    /// Stats of the units which can be bought, and how their damage is resisted
+    #[serde(default)]
+    pub catalogue: UnitCatalogue,
+    /// Upgrades players can research
+    #[serde(default)]
+    pub tech_tree: TechTree,
+    /// Players playing together, the others play alone. Empty for a free-for-all.
+    #[serde(default)]
+    pub teams: Vec<Vec<PlayerIndex>>,
+    /// Prepared situation and objectives, None for a regular match
+    #[serde(default)]
+    pub scenario: Option<Scenario>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            turn_duration_in_seconds: TURN_DURATION_IN_SECONDS,
+            money_batch: NEW_MONEY_BATCH,
+            map: Map::default(),
+            seed: DEFAULT_SEED,
+            catalogue: UnitCatalogue::default(),
+            tech_tree: TechTree::default(),
+            teams: Vec::new(),
+            scenario: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Team of the player, those in no team are alone in a team after the listed ones
+    pub fn team_of(&self, player: PlayerIndex) -> TeamId {
+        self.teams
+            .iter()
+            .position(|team| team.contains(&player))
+            .unwrap_or(self.teams.len() + player)
+    }
+
+    /// Players of the same team, a player is its own ally
+    pub fn are_allies(&self, player: PlayerIndex, other: PlayerIndex) -> bool {
+        self.team_of(player) == self.team_of(other)
+    }
+}
+
+/// Description of a match, given to observers before the first turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameInfo {
+    pub engine_version: String,
+    pub config: GameConfig,
+    pub players: Vec<String>,
+}
+
+impl GameInfo {
+    pub fn new(config: GameConfig, players: Vec<String>) -> Self {
+        GameInfo {
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            config,
+            players,
+        }
+    }
+}
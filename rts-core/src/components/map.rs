@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::Coordinate;
+use crate::entity::player::PlayerIndex;
+use crate::entity::unit::UnitType;
+
+const DEFAULT_WIDTH: u32 = 24;
+const DEFAULT_HEIGHT: u32 = 12;
+const BARRACK_VISION: i16 = 6;
+const BANK_VISION: i16 = 4;
+/// Longest side of a map, in tiles
+pub const MAX_SIDE: u32 = 256;
+
+/// Ground of a tile of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Terrain {
+    Plain,
+    Forest,
+    Water,
+    Wall,
+    HighGround,
+}
+
+impl Terrain {
+    /// Level of the ground units stand on
+    pub fn get_elevation(&self) -> i8 {
+        match &self {
+            Terrain::HighGround => 1,
+            Terrain::Plain | Terrain::Forest | Terrain::Water | Terrain::Wall => 0,
+        }
+    }
+
+    /// Units can stand and walk on the tile
+    pub fn is_walkable(&self) -> bool {
+        !matches!(self, Terrain::Water | Terrain::Wall)
+    }
+
+    /// Level up to which the tile hides what is behind it
+    pub fn get_height(&self) -> i8 {
+        match &self {
+            Terrain::Wall => i8::MAX,
+            Terrain::Forest | Terrain::HighGround => 1,
+            Terrain::Plain | Terrain::Water => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildingKind {
+    Barrack,
+    Bank,
+}
+
+impl BuildingKind {
+    /// How far the building sees through the fog of war, in tiles
+    pub fn get_vision(&self) -> i16 {
+        match &self {
+            BuildingKind::Barrack => BARRACK_VISION,
+            BuildingKind::Bank => BANK_VISION,
+        }
+    }
+}
+
+/// A building standing on the map for one of the players
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildingSite {
+    pub kind: BuildingKind,
+    pub owner: PlayerIndex,
+    pub coordinate: Coordinate,
+}
+
+/// A neutral unit guarding a place of the map
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreepCamp {
+    pub unit_type: UnitType,
+    pub coordinate: Coordinate,
+}
+
+/// A place taken by the player standing alone around it, rewarding its owner every turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturePoint {
+    pub coordinate: Coordinate,
+    /// Tiles around the point units have to stand in to take it
+    pub radius: i16,
+    /// Money given to the owner each turn
+    #[serde(default)]
+    pub income: i32,
+    /// Score given to the owner each turn
+    #[serde(default)]
+    pub score: i32,
+}
+
+/// Terrain and buildings of a game, tiles are one unit of coordinate wide
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MapFile")]
+pub struct Map {
+    width: u32,
+    height: u32,
+    /// Row by row, from the top left corner
+    tiles: Vec<Terrain>,
+    buildings: Vec<BuildingSite>,
+    #[serde(default)]
+    creeps: Vec<CreepCamp>,
+    #[serde(default)]
+    capture_points: Vec<CapturePoint>,
+}
+
+/// A map as it is written in files, checked before it is played on
+#[derive(Deserialize)]
+struct MapFile {
+    width: u32,
+    height: u32,
+    tiles: Vec<Terrain>,
+    buildings: Vec<BuildingSite>,
+    #[serde(default)]
+    creeps: Vec<CreepCamp>,
+    #[serde(default)]
+    capture_points: Vec<CapturePoint>,
+}
+
+impl TryFrom<MapFile> for Map {
+    type Error = String;
+
+    fn try_from(file: MapFile) -> Result<Self, Self::Error> {
+        if file.width > MAX_SIDE || file.height > MAX_SIDE {
+            return Err(format!(
+                "Maps are at most {} tiles wide and high, not {}x{}",
+                MAX_SIDE, file.width, file.height
+            ));
+        }
+        if file.tiles.len() != file.width as usize * file.height as usize {
+            return Err(format!(
+                "Map of {}x{} tiles has {} tiles",
+                file.width,
+                file.height,
+                file.tiles.len()
+            ));
+        }
+        Ok(Map {
+            width: file.width,
+            height: file.height,
+            tiles: file.tiles,
+            buildings: file.buildings,
+            creeps: file.creeps,
+            capture_points: file.capture_points,
+        })
+    }
+}
+
+impl Default for Map {
+    /// Open field with a barrack and a bank in opposite corners for two players
+    fn default() -> Self {
+        let (width, height) = (DEFAULT_WIDTH, DEFAULT_HEIGHT);
+        let (right, bottom) = (width as i32 - 2, height as i32 - 2);
+        Map::new(width, height).with_buildings(vec![
+            BuildingSite {
+                kind: BuildingKind::Barrack,
+                owner: 0,
+                coordinate: Coordinate::from_tiles(1, 1),
+            },
+            BuildingSite {
+                kind: BuildingKind::Bank,
+                owner: 0,
+                coordinate: Coordinate::from_tiles(2, 1),
+            },
+            BuildingSite {
+                kind: BuildingKind::Barrack,
+                owner: 1,
+                coordinate: Coordinate::from_tiles(right, bottom),
+            },
+            BuildingSite {
+                kind: BuildingKind::Bank,
+                owner: 1,
+                coordinate: Coordinate::from_tiles(right - 1, bottom),
+            },
+        ])
+    }
+}
+
+impl Map {
+    /// Create a map full of plains without any building
+    pub fn new(width: u32, height: u32) -> Self {
+        Map {
+            width,
+            height,
+            tiles: vec![Terrain::Plain; width as usize * height as usize],
+            buildings: Vec::new(),
+            creeps: Vec::new(),
+            capture_points: Vec::new(),
+        }
+    }
+
+    pub fn with_buildings(mut self, buildings: Vec<BuildingSite>) -> Self {
+        self.buildings = buildings;
+        self
+    }
+
+    pub fn with_creeps(mut self, creeps: Vec<CreepCamp>) -> Self {
+        self.creeps = creeps;
+        self
+    }
+
+    pub fn with_capture_points(mut self, capture_points: Vec<CapturePoint>) -> Self {
+        self.capture_points = capture_points;
+        self
+    }
+
+    pub fn get_width(&self) -> &u32 {
+        &self.width
+    }
+
+    pub fn get_height(&self) -> &u32 {
+        &self.height
+    }
+
+    pub fn get_buildings(&self) -> &[BuildingSite] {
+        &self.buildings
+    }
+
+    pub fn get_creeps(&self) -> &[CreepCamp] {
+        &self.creeps
+    }
+
+    pub fn get_capture_points(&self) -> &[CapturePoint] {
+        &self.capture_points
+    }
+
+    pub fn get_terrain(&self, x: u32, y: u32) -> Option<&Terrain> {
+        self.index_of(x, y).and_then(|i| self.tiles.get(i))
+    }
+
+    pub fn set_terrain(&mut self, x: u32, y: u32, terrain: Terrain) {
+        if let Some(i) = self.index_of(x, y) {
+            self.tiles[i] = terrain;
+        }
+    }
+
+    /// Tiles out of the map can not be walked on
+    pub fn is_walkable(&self, x: u32, y: u32) -> bool {
+        self.get_terrain(x, y)
+            .map(Terrain::is_walkable)
+            .unwrap_or(false)
+    }
+
+    /// Tile holding the coordinate, if it is on the map.
+    /// Integer coordinates are the centers of the tiles.
+    pub fn tile_of(&self, coordinate: &Coordinate) -> Option<(u32, u32)> {
+        let (x, y) = coordinate.to_tile();
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            None
+        } else {
+            Some((x as u32, y as u32))
+        }
+    }
+
+    /// Terrain under the coordinate, plains out of the map
+    pub fn terrain_at(&self, coordinate: &Coordinate) -> Terrain {
+        self.tile_of(coordinate)
+            .and_then(|(x, y)| self.get_terrain(x, y))
+            .copied()
+            .unwrap_or(Terrain::Plain)
+    }
+
+    /// True when no tile between the two coordinates is higher than both ends.
+    /// Walls always block, forests and hills only hide from the low ground.
+    /// The line goes from tile to tile so both directions give the same answer.
+    pub fn has_line_of_sight(&self, from: &Coordinate, to: &Coordinate) -> bool {
+        let eye = self
+            .terrain_at(from)
+            .get_elevation()
+            .max(self.terrain_at(to).get_elevation());
+        let (start, end) = (from.to_tile(), to.to_tile());
+        tiles_between(start, end).into_iter().all(|(x, y)| {
+            let height = if x < 0 || y < 0 {
+                0
+            } else {
+                self.get_terrain(x as u32, y as u32)
+                    .map(|terrain| terrain.get_height())
+                    .unwrap_or(0)
+            };
+            height <= eye
+        })
+    }
+
+    fn index_of(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// Tiles crossed by the line between two tiles, without the ends (Bresenham).
+/// The line is always drawn from the smallest tile so it is symmetric.
+fn tiles_between(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    let (start, end) = if start <= end {
+        (start, end)
+    } else {
+        (end, start)
+    };
+    let (dx, dy) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+    let (sx, sy) = (
+        if start.0 < end.0 { 1 } else { -1 },
+        if start.1 < end.1 { 1 } else { -1 },
+    );
+    let (mut x, mut y) = start;
+    let mut error = dx + dy;
+    let mut tiles = Vec::new();
+    while (x, y) != end {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        if (x, y) != end {
+            tiles.push((x, y));
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod test_map {
+
+    use crate::components::game::test_game::{duel_on, walk, TestClientGameState};
+    use crate::components::play_ground::Coordinate;
+    use crate::entity::game_actions::Action;
+    use crate::entity::unit::UnitType;
+
+    use super::{CapturePoint, CreepCamp, Map, Terrain};
+
+    #[test]
+    pub fn should_block_line_of_sight() {
+        let mut map = Map::new(8, 5);
+        let (left, right) = (Coordinate::from_tiles(0, 2), Coordinate::from_tiles(6, 2));
+        assert!(map.has_line_of_sight(&left, &right));
+
+        map.set_terrain(3, 2, Terrain::Forest);
+        assert!(!map.has_line_of_sight(&left, &right));
+        assert!(!map.has_line_of_sight(&right, &left));
+        // Trees do not hide a unit standing in the forest
+        assert!(map.has_line_of_sight(&left, &Coordinate::from_tiles(3, 2)));
+
+        // The high ground sees over trees but not over walls
+        map.set_terrain(0, 2, Terrain::HighGround);
+        assert!(map.has_line_of_sight(&left, &right));
+        map.set_terrain(4, 2, Terrain::Wall);
+        assert!(!map.has_line_of_sight(&left, &right));
+    }
+
+    #[test]
+    pub fn should_reject_malformed_map_files() {
+        let map = Map::new(3, 2);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(map, serde_json::from_str::<Map>(&json).unwrap());
+
+        let missing = json.replacen("\"Plain\",", "", 1);
+        assert!(serde_json::from_str::<Map>(&missing).is_err());
+        let huge = json.replace("\"width\":3", "\"width\":4000000000");
+        assert!(serde_json::from_str::<Map>(&huge).is_err());
+    }
+
+    #[test]
+    pub fn should_fight_creeps_and_hold_capture_points() {
+        let map = Map::new(24, 12)
+            .with_creeps(vec![CreepCamp {
+                unit_type: UnitType::Archer,
+                coordinate: Coordinate::from_tiles(20, 10),
+            }])
+            .with_capture_points(vec![CapturePoint {
+                coordinate: Coordinate::from_tiles(3, 3),
+                radius: 1,
+                income: 10,
+                score: 3,
+            }]);
+        let game = duel_on(map, TestClientGameState());
+        assert_eq!(&None, game.snapshot().units[0].unit.get_owner());
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::MoveUnit(2, Coordinate::from_tiles(3, 3)))
+            .unwrap();
+        walk(&game);
+        game.hold_capture_points().unwrap();
+        let observation = game.observe(0).unwrap();
+        assert_eq!(vec![Some(0)], observation.captures);
+        assert_eq!(90, observation.money);
+
+        // A contested point stays with its owner
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::MoveUnit(3, Coordinate::from_tiles(2, 3)))
+            .unwrap();
+        walk(&game);
+        game.hold_capture_points().unwrap();
+        assert_eq!(vec![Some(0)], game.observe(1).unwrap().captures);
+
+        game.play(0, Action::MoveUnit(2, Coordinate::from_tiles(13, 10)))
+            .unwrap();
+        walk(&game);
+        game.command_creeps().unwrap();
+        let snapshot = game.snapshot();
+        assert!(snapshot.units[1].unit.get_health() < snapshot.units[1].unit.get_max_health());
+        for _ in 0..4 {
+            game.begin_turn().unwrap();
+            game.execute_orders().unwrap();
+            game.command_creeps().unwrap();
+        }
+        let result = game.match_result();
+        assert_eq!(25, result.breakdowns[0].creeps);
+        assert_eq!(6, result.breakdowns[0].objectives);
+        assert_eq!(Some(&31), result.get_score(0));
+    }
+}
@@ -0,0 +1,473 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::map::{BuildingKind, BuildingSite, CapturePoint, Map, Terrain, MAX_SIDE};
+use crate::components::play_ground::{Coordinate, TILE_SCALE};
+use crate::components::rng::GameRng;
+use crate::exceptions::RtsException;
+
+/// Smallest side of a generated map, in tiles
+const MIN_SIDE: u32 = 16;
+/// Tiles between the first base and the edges of the map
+const BASE_MARGIN: i32 = 2;
+/// Tiles kept open around a base
+const BASE_RADIUS: i32 = 3;
+/// Bases are walled in this far away, the openings are the chokepoints
+const BASE_WALL: i32 = BASE_RADIUS + 2;
+/// Tiles kept open around the middle of the map, where every base is linked
+const CENTER_RADIUS: i32 = 2;
+const SMOOTHING_PASSES: usize = 2;
+/// Share of the open ground covered by forests, in percent
+const FOREST_DENSITY: u8 = 40;
+const RESOURCE_RADIUS: i16 = 2;
+const RESOURCE_INCOME: i32 = 10;
+const RESOURCE_SCORE: i32 = 1;
+/// Places tried for a resource before giving up on it
+const RESOURCE_ATTEMPTS: usize = 100;
+/// Closest two resources, or a resource and a base, can be in tiles
+const RESOURCE_SPACING: i32 = 4;
+
+/// How the map repeats itself, so that every player starts with the same chances
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Symmetry {
+    /// Turned around the middle of the map, by half a turn for 2 players
+    /// and by a quarter for 4, which needs a square map
+    #[default]
+    Point,
+    /// Reflected left to right, and top to bottom as well for 4 players
+    Mirror,
+}
+
+/// How to generate a map, the same settings always give the same map
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapSettings {
+    pub width: u32,
+    pub height: u32,
+    /// 2 or 4
+    pub players: usize,
+    pub symmetry: Symmetry,
+    pub seed: u64,
+    /// Share of the map covered by water and walls, in percent
+    pub obstacles: u8,
+    /// Capture points giving money near each player, another one stands in the middle
+    pub resources: usize,
+}
+
+impl Default for MapSettings {
+    fn default() -> Self {
+        MapSettings {
+            width: 32,
+            height: 32,
+            players: 2,
+            symmetry: Symmetry::default(),
+            seed: 0,
+            obstacles: 45,
+            resources: 1,
+        }
+    }
+}
+
+impl MapSettings {
+    /// Build a map with a barrack and a bank for each player. Every walkable tile
+    /// can be reached from every base, and bases are walled in with narrow openings.
+    pub fn generate(&self) -> Result<Map, RtsException> {
+        self.validate()?;
+        let mut rng = GameRng::new(self.seed);
+        let blocked = self.smooth(self.noise(&mut rng, self.obstacles));
+        let wild = self.smooth(self.noise(&mut rng, FOREST_DENSITY));
+
+        let mut map = Map::new(self.width, self.height);
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let i = self.index(x, y);
+                let terrain = match (blocked[i], wild[i]) {
+                    (true, true) => Terrain::Water,
+                    (true, false) => Terrain::Wall,
+                    (false, true) => Terrain::Forest,
+                    (false, false) => Terrain::Plain,
+                };
+                map.set_terrain(x as u32, y as u32, terrain);
+            }
+        }
+
+        let base = self.first_base();
+        let center = ((self.width as i32 - 1) / 2, (self.height as i32 - 1) / 2);
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let from_base = (x - base.0).abs().max((y - base.1).abs());
+                if from_base == BASE_WALL {
+                    self.paint(&mut map, (x, y), Terrain::Wall);
+                }
+            }
+        }
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let from_base = (x - base.0).abs().max((y - base.1).abs());
+                if from_base <= BASE_RADIUS || self.is_center(x, y) {
+                    self.paint(&mut map, (x, y), Terrain::Plain);
+                }
+            }
+        }
+        // One way out towards the middle, another one somewhere else
+        self.carve(&mut map, base, center);
+        let side = rng.range(0, 2) == 0;
+        let along = rng.range(-BASE_WALL as i64, BASE_WALL as i64 + 1) as i32;
+        let exit = if side {
+            (base.0 + BASE_WALL + 1, base.1 + along)
+        } else {
+            (base.0 + along, base.1 + BASE_WALL + 1)
+        };
+        self.carve(&mut map, base, exit);
+        self.fill_pockets(&mut map, base);
+
+        let bank = (base.0 + 1, base.1);
+        let buildings = self
+            .images(base)
+            .into_iter()
+            .zip(self.images(bank))
+            .enumerate()
+            .flat_map(|(owner, (barrack, bank))| {
+                [
+                    BuildingSite {
+                        kind: BuildingKind::Barrack,
+                        owner,
+                        coordinate: Coordinate::from_tiles(barrack.0, barrack.1),
+                    },
+                    BuildingSite {
+                        kind: BuildingKind::Bank,
+                        owner,
+                        coordinate: Coordinate::from_tiles(bank.0, bank.1),
+                    },
+                ]
+            })
+            .collect();
+        let capture_points = self.place_resources(&mut rng, &map, base);
+        Ok(map
+            .with_buildings(buildings)
+            .with_capture_points(capture_points))
+    }
+
+    fn validate(&self) -> Result<(), RtsException> {
+        if self.players != 2 && self.players != 4 {
+            return Err(RtsException::GeneralException(format!(
+                "Maps are generated for 2 or 4 players, not {}",
+                self.players
+            )));
+        }
+        if self.width < MIN_SIDE || self.height < MIN_SIDE {
+            return Err(RtsException::GeneralException(format!(
+                "Generated maps are at least {} tiles wide and high",
+                MIN_SIDE
+            )));
+        }
+        if self.width > MAX_SIDE || self.height > MAX_SIDE {
+            return Err(RtsException::GeneralException(format!(
+                "Generated maps are at most {} tiles wide and high",
+                MAX_SIDE
+            )));
+        }
+        if self.players == 4 && self.symmetry == Symmetry::Point && self.width != self.height {
+            return Err(RtsException::GeneralException(
+                "A quarter turn symmetry needs a square map".to_string(),
+            ));
+        }
+        if self.obstacles > 100 {
+            return Err(RtsException::GeneralException(format!(
+                "Obstacles cover {}% of the map",
+                self.obstacles
+            )));
+        }
+        Ok(())
+    }
+
+    /// Where the tile of the first player is for each player, in the order of players
+    fn images(&self, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+        let (right, bottom) = (self.width as i32 - 1, self.height as i32 - 1);
+        match (self.symmetry, self.players) {
+            (Symmetry::Point, 2) => vec![(x, y), (right - x, bottom - y)],
+            (Symmetry::Mirror, 2) => vec![(x, y), (right - x, y)],
+            (Symmetry::Point, _) => vec![
+                (x, y),
+                (right - y, x),
+                (right - x, bottom - y),
+                (y, bottom - x),
+            ],
+            (Symmetry::Mirror, _) => vec![
+                (x, y),
+                (right - x, y),
+                (right - x, bottom - y),
+                (x, bottom - y),
+            ],
+        }
+    }
+
+    /// Barrack of the first player, in a corner or in the middle of the left side
+    fn first_base(&self) -> (i32, i32) {
+        match (self.symmetry, self.players) {
+            (Symmetry::Mirror, 2) => (BASE_MARGIN, (self.height as i32 - 1) / 2),
+            _ => (BASE_MARGIN, BASE_MARGIN),
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width as i32 + x) as usize
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    /// Same distance from the middle as from every side, so it is left alike by the symmetry
+    fn is_center(&self, x: i32, y: i32) -> bool {
+        (2 * x - (self.width as i32 - 1)).abs() <= 2 * CENTER_RADIUS
+            && (2 * y - (self.height as i32 - 1)).abs() <= 2 * CENTER_RADIUS
+    }
+
+    /// Random tiles drawn once for all the images of a tile
+    fn noise(&self, rng: &mut GameRng, percent: u8) -> Vec<bool> {
+        let mut drawn = vec![None; (self.width * self.height) as usize];
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if drawn[self.index(x, y)].is_some() {
+                    continue;
+                }
+                let value = rng.chance(percent);
+                for (x, y) in self.images((x, y)) {
+                    drawn[self.index(x, y)] = Some(value);
+                }
+            }
+        }
+        drawn.into_iter().map(Option::unwrap_or_default).collect()
+    }
+
+    /// Turn noise into blobs, a tile is set when most of its neighbours are.
+    /// Each tile only looks around it, so the map stays symmetric.
+    fn smooth(&self, mut mask: Vec<bool>) -> Vec<bool> {
+        for _ in 0..SMOOTHING_PASSES {
+            let mut next = mask.clone();
+            for y in 0..self.height as i32 {
+                for x in 0..self.width as i32 {
+                    let around = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                        .filter(|(x, y)| self.contains(*x, *y) && mask[self.index(*x, *y)])
+                        .count();
+                    next[self.index(x, y)] = around >= 5;
+                }
+            }
+            mask = next;
+        }
+        mask
+    }
+
+    /// Set the terrain of a tile for every player
+    fn paint(&self, map: &mut Map, tile: (i32, i32), terrain: Terrain) {
+        for (x, y) in self.images(tile) {
+            if self.contains(x, y) {
+                map.set_terrain(x as u32, y as u32, terrain);
+            }
+        }
+    }
+
+    /// Open a path two tiles wide between the tiles, for every player
+    fn carve(&self, map: &mut Map, from: (i32, i32), to: (i32, i32)) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = dx.abs().max(dy.abs()).max(1);
+        for step in 0..=steps {
+            let (x, y) = (from.0 + dx * step / steps, from.1 + dy * step / steps);
+            // Wide enough to never step diagonally between two obstacles
+            for tile in [(x, y), (x + 1, y), (x, y + 1)] {
+                self.paint(map, tile, Terrain::Plain);
+            }
+        }
+    }
+
+    /// Wall the walkable tiles which can not be reached from the base.
+    /// Bases reach each other through the middle, so all of them keep the same ground.
+    fn fill_pockets(&self, map: &mut Map, base: (i32, i32)) {
+        let reached = reachable(map, base);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if map.is_walkable(x, y) && !reached[(y * self.width + x) as usize] {
+                    map.set_terrain(x, y, Terrain::Wall);
+                }
+            }
+        }
+    }
+
+    /// Capture points closer to one base than to the others, the same for every player,
+    /// and one shared by all in the middle of the map
+    fn place_resources(&self, rng: &mut GameRng, map: &Map, base: (i32, i32)) -> Vec<CapturePoint> {
+        let bases = self.images(base);
+        let mut taken: Vec<(i32, i32)> = bases.clone();
+        for _ in 0..self.resources {
+            let found = (0..RESOURCE_ATTEMPTS).find_map(|_| {
+                let tile = (
+                    rng.range(0, self.width as i64) as i32,
+                    rng.range(0, self.height as i64) as i32,
+                );
+                let images = self.images(tile);
+                let fits = map.is_walkable(tile.0 as u32, tile.1 as u32)
+                    && !self.is_center(tile.0, tile.1)
+                    && bases[1..]
+                        .iter()
+                        .all(|other| squared(tile, *other) > squared(tile, base))
+                    && images.iter().enumerate().all(|(i, image)| {
+                        taken
+                            .iter()
+                            .chain(&images[i + 1..])
+                            .all(|other| chebyshev(*image, *other) >= RESOURCE_SPACING)
+                    });
+                fits.then_some(images)
+            });
+            // A crowded map gets fewer resources, never unfair ones
+            if let Some(images) = found {
+                taken.extend(images);
+            }
+        }
+
+        let middle = Coordinate::new(
+            (self.width as i32 - 1) * TILE_SCALE / 2,
+            (self.height as i32 - 1) * TILE_SCALE / 2,
+        );
+        taken[bases.len()..]
+            .iter()
+            .map(|(x, y)| Coordinate::from_tiles(*x, *y))
+            .chain([middle])
+            .map(|coordinate| CapturePoint {
+                coordinate,
+                radius: RESOURCE_RADIUS,
+                income: RESOURCE_INCOME,
+                score: RESOURCE_SCORE,
+            })
+            .collect()
+    }
+}
+
+/// Walkable tiles reached from the tile, walking to side tiles only
+fn reachable(map: &Map, from: (i32, i32)) -> Vec<bool> {
+    let (width, height) = (*map.get_width() as i32, *map.get_height() as i32);
+    let mut reached = vec![false; (width * height) as usize];
+    let mut pending = vec![from];
+    while let Some((x, y)) = pending.pop() {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            continue;
+        }
+        let i = (y * width + x) as usize;
+        if reached[i] || !map.is_walkable(x as u32, y as u32) {
+            continue;
+        }
+        reached[i] = true;
+        pending.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+    }
+    reached
+}
+
+fn squared(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2)
+}
+
+fn chebyshev(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+#[cfg(test)]
+mod test_map_generator {
+
+    use crate::components::map::{BuildingKind, Map};
+    use crate::components::pathfinding::find_path;
+
+    use super::{reachable, MapSettings, Symmetry};
+
+    fn check_fairness(settings: &MapSettings, map: &Map) {
+        let (width, height) = (settings.width as i32, settings.height as i32);
+        for y in 0..height {
+            for x in 0..width {
+                let terrain = map.get_terrain(x as u32, y as u32);
+                for (ix, iy) in settings.images((x, y)) {
+                    assert_eq!(terrain, map.get_terrain(ix as u32, iy as u32));
+                }
+            }
+        }
+
+        let barracks: Vec<_> = map
+            .get_buildings()
+            .iter()
+            .filter(|site| site.kind == BuildingKind::Barrack)
+            .collect();
+        assert_eq!(settings.players, barracks.len());
+        let (bx, by) = barracks[0].coordinate.to_tile();
+        let reached = reachable(map, (bx, by));
+        for y in 0..height {
+            for x in 0..width {
+                let walkable = map.is_walkable(x as u32, y as u32);
+                assert_eq!(walkable, reached[(y * width + x) as usize]);
+            }
+        }
+        for point in map.get_capture_points() {
+            for barrack in barracks.iter() {
+                assert!(find_path(map, &barrack.coordinate, &point.coordinate).is_some());
+            }
+        }
+        assert_eq!(
+            1 + settings.players * settings.resources,
+            map.get_capture_points().len()
+        );
+    }
+
+    #[test]
+    pub fn should_generate_fair_maps() {
+        for (players, symmetry) in [
+            (2, Symmetry::Point),
+            (2, Symmetry::Mirror),
+            (4, Symmetry::Point),
+            (4, Symmetry::Mirror),
+        ] {
+            for seed in 0..4 {
+                let settings = MapSettings {
+                    players,
+                    symmetry,
+                    seed,
+                    ..MapSettings::default()
+                };
+                check_fairness(&settings, &settings.generate().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    pub fn should_repeat_with_same_seed() {
+        let settings = MapSettings {
+            seed: 42,
+            ..MapSettings::default()
+        };
+        let map = settings.generate().unwrap();
+        assert_eq!(map, settings.generate().unwrap());
+        let other = MapSettings {
+            seed: 7,
+            ..MapSettings::default()
+        };
+        assert_ne!(map, other.generate().unwrap());
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(map, serde_json::from_str::<Map>(&json).unwrap());
+    }
+
+    #[test]
+    pub fn should_reject_unfair_settings() {
+        let three = MapSettings {
+            players: 3,
+            ..MapSettings::default()
+        };
+        assert!(three.generate().is_err());
+        let wide = MapSettings {
+            players: 4,
+            width: 40,
+            ..MapSettings::default()
+        };
+        assert!(wide.generate().is_err());
+        let mirrored = MapSettings {
+            symmetry: Symmetry::Mirror,
+            ..wide
+        };
+        assert!(mirrored.generate().is_ok());
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::player::{PlayerIndex, TeamId};
+
+/// What a player earned points for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreSource {
+    /// Units of other players killed
+    Kill,
+    /// Neutral units killed
+    Creep,
+    /// Capture points held
+    Objective,
+}
+
+/// Score of a player, split by what it was earned for
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub kills: i32,
+    pub creeps: i32,
+    pub objectives: i32,
+}
+
+impl ScoreBreakdown {
+    pub fn add(&mut self, source: ScoreSource, amount: i32) {
+        let points = match source {
+            ScoreSource::Kill => &mut self.kills,
+            ScoreSource::Creep => &mut self.creeps,
+            ScoreSource::Objective => &mut self.objectives,
+        };
+        *points = points.saturating_add(amount);
+    }
+}
+
+/// Outcome of a game, as computed by the engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchResult {
+    /// Score of each player, in the order of the game
+    pub scores: Vec<i32>,
+    /// Player alone in the winning team, none on a draw or when a team of several won
+    pub winner: Option<PlayerIndex>,
+    /// Team of each player, in the order of the game
+    #[serde(default)]
+    pub teams: Vec<TeamId>,
+    /// Team with the best total score, none on a draw
+    #[serde(default)]
+    pub winning_team: Option<TeamId>,
+    /// Where the score of each player comes from, in the order of the game
+    #[serde(default)]
+    pub breakdowns: Vec<ScoreBreakdown>,
+}
+
+impl MatchResult {
+    /// Result of a free-for-all, every player is alone in its team
+    pub fn from_scores(scores: Vec<i32>) -> Self {
+        let teams = (0..scores.len()).collect();
+        Self::from_team_scores(scores, teams)
+    }
+
+    /// The team whose players scored the most in total wins
+    pub fn from_team_scores(scores: Vec<i32>, teams: Vec<TeamId>) -> Self {
+        let mut totals: BTreeMap<TeamId, i32> = BTreeMap::new();
+        for (score, team) in scores.iter().zip(teams.iter()) {
+            let total = totals.entry(*team).or_default();
+            *total = total.saturating_add(*score);
+        }
+        let best = totals.values().max();
+        let winning_team = match best {
+            Some(best) if totals.values().filter(|total| *total == best).count() == 1 => totals
+                .iter()
+                .find(|(_, total)| *total == best)
+                .map(|(team, _)| *team),
+            _ => None,
+        };
+        let members: Vec<PlayerIndex> = (0..teams.len())
+            .filter(|player| Some(teams[*player]) == winning_team)
+            .collect();
+        let winner = match members[..] {
+            [player] => Some(player),
+            _ => None,
+        };
+        MatchResult {
+            scores,
+            winner,
+            teams,
+            winning_team,
+            breakdowns: Vec::new(),
+        }
+    }
+
+    pub fn with_breakdowns(mut self, breakdowns: Vec<ScoreBreakdown>) -> Self {
+        self.breakdowns = breakdowns;
+        self
+    }
+
+    pub fn get_score(&self, index: PlayerIndex) -> Option<&i32> {
+        self.scores.get(index)
+    }
+
+    pub fn get_team(&self, index: PlayerIndex) -> Option<&TeamId> {
+        self.teams.get(index)
+    }
+
+    /// True if the team of the player won
+    pub fn is_winner(&self, index: PlayerIndex) -> bool {
+        match self.teams.get(index) {
+            Some(team) => self.winning_team == Some(*team),
+            None => self.winner == Some(index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_match_result {
+
+    use super::MatchResult;
+
+    #[test]
+    pub fn should_find_winner() {
+        let result = MatchResult::from_scores(vec![20, 40, 0]);
+        assert_eq!(Some(1), result.winner);
+        assert!(result.is_winner(1));
+        assert_eq!(Some(&0), result.get_score(2));
+
+        assert_eq!(None, MatchResult::from_scores(vec![20, 20]).winner);
+        assert_eq!(None, MatchResult::from_scores(Vec::new()).winner);
+    }
+
+    #[test]
+    pub fn should_make_teams_win_together() {
+        let result = MatchResult::from_team_scores(vec![50, 0, 20, 40], vec![0, 0, 1, 1]);
+        assert_eq!(Some(1), result.winning_team);
+        assert_eq!(None, result.winner);
+        assert!(result.is_winner(2));
+        assert!(result.is_winner(3));
+        assert!(!result.is_winner(0));
+
+        let draw = MatchResult::from_team_scores(vec![30, 10, 40], vec![0, 0, 1]);
+        assert_eq!(None, draw.winning_team);
+        assert!(!draw.is_winner(2));
+    }
+}
@@ -1,7 +1,26 @@
 pub mod building;
 pub mod unit_factory;
+pub mod unit_catalogue;
+pub mod collision;
 pub mod game;
+pub mod game_config;
+pub mod event_log;
+pub mod formation;
+pub mod map;
+pub mod map_generator;
+pub mod match_result;
+pub mod observation;
+pub mod pathfinding;
 pub mod play_ground;
+pub mod replay;
+pub mod rng;
+pub mod scenario;
+pub mod snapshot;
+pub mod state_hash;
 pub mod displayer;
+pub mod ascii_renderer;
+pub mod svg_renderer;
+pub mod tech_tree;
 pub mod turn_strategy;
+pub mod visibility;
 
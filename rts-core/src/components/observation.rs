@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use crate::components::play_ground::{Coordinate, Identifier};
+use crate::components::snapshot::UnitSnapshot;
+use crate::components::tech_tree::ResearchState;
+use crate::components::visibility::{LastKnown, VisibilityMap};
+use crate::entity::game_event::Turn;
+use crate::entity::order::OrderKind;
+use crate::entity::player::{GroupName, PlayerIndex};
+
+/// What a player is allowed to know about the game at a given turn,
+/// enemies under the fog of war are left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub turn: Turn,
+    pub player: PlayerIndex,
+    pub money: i32,
+    /// Units of the player, sorted by identifier
+    pub units: Vec<UnitSnapshot>,
+    /// Enemy units in sight, sorted by identifier
+    pub enemies: Vec<UnitSnapshot>,
+    /// Units of the other players of the team, always in sight
+    pub allies: Vec<UnitSnapshot>,
+    /// Enemy units seen before and now hidden, where they were last seen
+    pub last_known: Vec<LastKnown>,
+    pub visibility: VisibilityMap,
+    /// Technologies of every player, in the order of the game
+    pub research: Vec<ResearchState>,
+    /// Move orders of the units of the player, sorted by identifier
+    pub orders: Vec<OrderSnapshot>,
+    /// Owner of each capture point of the map
+    pub captures: Vec<Option<PlayerIndex>>,
+    /// Control groups of the player
+    pub groups: BTreeMap<GroupName, Vec<Identifier>>,
+}
+
+/// Standing order of a unit, where it is walking to and when it should get there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderSnapshot {
+    pub identifier: Identifier,
+    pub order: OrderKind,
+    /// None while the unit stands
+    pub destination: Option<Coordinate>,
+    /// Turns left, none while the unit can not move
+    pub eta: Option<u32>,
+}
@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::components::map::Map;
+use crate::components::play_ground::Coordinate;
+
+/// Cost of a step to a side tile, and to a corner tile
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Shortest way on walkable tiles, as the centers of the tiles to cross
+/// followed by the exact destination. Diagonal steps never cut the corner
+/// of a tile which can not be walked on. None when the destination can
+/// not be reached. The same map always gives the same path.
+pub fn find_path(map: &Map, from: &Coordinate, to: &Coordinate) -> Option<Vec<Coordinate>> {
+    let goal = map.tile_of(to)?;
+    if !map.is_walkable(goal.0, goal.1) {
+        return None;
+    }
+    let start = match map.tile_of(from) {
+        Some(start) => start,
+        None => return Some(vec![*to]),
+    };
+    if start == goal {
+        return Some(vec![*to]);
+    }
+
+    let width = *map.get_width() as usize;
+    let index = |(x, y): (u32, u32)| y as usize * width + x as usize;
+    let size = width * *map.get_height() as usize;
+    let mut costs = vec![u32::MAX; size];
+    let mut previous: Vec<Option<(u32, u32)>> = vec![None; size];
+    let mut open = BinaryHeap::new();
+    costs[index(start)] = 0;
+    open.push(Reverse((heuristic(start, goal), index(start), start)));
+
+    while let Some(Reverse((_, _, tile))) = open.pop() {
+        if tile == goal {
+            break;
+        }
+        let cost = costs[index(tile)];
+        for (dx, dy) in NEIGHBOURS {
+            let (x, y) = (tile.0 as i32 + dx, tile.1 as i32 + dy);
+            if !walkable(map, x, y) {
+                continue;
+            }
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal
+                && !(walkable(map, tile.0 as i32 + dx, tile.1 as i32)
+                    && walkable(map, tile.0 as i32, tile.1 as i32 + dy))
+            {
+                continue;
+            }
+            let next = (x as u32, y as u32);
+            let step = if diagonal {
+                DIAGONAL_COST
+            } else {
+                STRAIGHT_COST
+            };
+            if cost + step < costs[index(next)] {
+                costs[index(next)] = cost + step;
+                previous[index(next)] = Some(tile);
+                open.push(Reverse((
+                    cost + step + heuristic(next, goal),
+                    index(next),
+                    next,
+                )));
+            }
+        }
+    }
+
+    previous[index(goal)]?;
+    let mut tiles = Vec::new();
+    let mut tile = previous[index(goal)];
+    while let Some(current) = tile.filter(|current| *current != start) {
+        tiles.push(Coordinate::from_tiles(current.0 as i32, current.1 as i32));
+        tile = previous[index(current)];
+    }
+    tiles.reverse();
+    tiles.push(*to);
+    Some(tiles)
+}
+
+fn walkable(map: &Map, x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && map.is_walkable(x as u32, y as u32)
+}
+
+/// Cost of the way without obstacles, never more than the real one
+fn heuristic(from: (u32, u32), to: (u32, u32)) -> u32 {
+    let dx = from.0.abs_diff(to.0);
+    let dy = from.1.abs_diff(to.1);
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+#[cfg(test)]
+mod test_pathfinding {
+
+    use crate::components::map::{Map, Terrain};
+    use crate::components::play_ground::Coordinate;
+
+    use super::find_path;
+
+    #[test]
+    pub fn should_go_around_walls() {
+        let mut map = Map::new(5, 3);
+        map.set_terrain(2, 0, Terrain::Wall);
+        map.set_terrain(2, 1, Terrain::Water);
+        let to = Coordinate::new(4000, 200);
+
+        let path = find_path(&map, &Coordinate::default(), &to).unwrap();
+        assert_eq!(Some(&to), path.last());
+        assert!(path.contains(&Coordinate::from_tiles(2, 2)));
+        assert_eq!(path, find_path(&map, &Coordinate::default(), &to).unwrap());
+
+        map.set_terrain(2, 2, Terrain::Wall);
+        assert_eq!(None, find_path(&map, &Coordinate::default(), &to));
+        assert_eq!(
+            None,
+            find_path(&map, &Coordinate::default(), &Coordinate::from_tiles(2, 0))
+        );
+        assert_eq!(
+            None,
+            find_path(&map, &Coordinate::default(), &Coordinate::from_tiles(9, 0))
+        );
+    }
+}
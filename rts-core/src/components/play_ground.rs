@@ -2,6 +2,9 @@ use std::cell::RefCell;
 use std::fmt::Display;
 use std::rc::Rc;
 
+use serde::{Deserialize, Serialize};
+
+use crate::components::state_hash::{StableHash, StateHasher};
 use crate::exceptions::RtsException;
 
 pub trait PlayGroundObserver<T>
@@ -18,10 +21,109 @@ where
 }
 
 pub type Cell<T> = Rc<RefCell<UnitHolder<T>>>;
-pub type Coordinate = (f32, f32);
-
 pub type Identifier = i128;
 
+/// Number of coordinate steps in a tile of the map
+pub const TILE_SCALE: i32 = 1000;
+
+/// Position on the map, in thousandths of a tile.
+/// Only integers are used by the engine so that a game plays the same way
+/// on every machine, floats are only accepted and given back at the edges.
+/// Integer tiles are the centers of the tiles of the map.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Coordinate {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Coordinate {
+    /// Create a coordinate from thousandths of a tile
+    pub fn new(x: i32, y: i32) -> Self {
+        Coordinate { x, y }
+    }
+
+    /// Create a coordinate at the center of a tile, clamped to the range of coordinates
+    pub fn from_tiles(x: i32, y: i32) -> Self {
+        Coordinate::new(x.saturating_mul(TILE_SCALE), y.saturating_mul(TILE_SCALE))
+    }
+
+    /// Position in tiles, for displaying only
+    pub fn to_tiles(&self) -> (f32, f32) {
+        (
+            self.x as f32 / TILE_SCALE as f32,
+            self.y as f32 / TILE_SCALE as f32,
+        )
+    }
+
+    /// Tile holding the coordinate, it may be out of the map
+    pub fn to_tile(&self) -> (i32, i32) {
+        // In i64, coordinates close to the limits come from untrusted replays
+        let tile = |value: i32| {
+            (value as i64 + (TILE_SCALE / 2) as i64).div_euclid(TILE_SCALE as i64) as i32
+        };
+        (tile(self.x), tile(self.y))
+    }
+
+    /// Squared distance to the other coordinate, exact
+    pub fn squared_distance(&self, other: &Coordinate) -> i64 {
+        let (dx, dy) = (
+            (other.x as i64) - (self.x as i64),
+            (other.y as i64) - (self.y as i64),
+        );
+        dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))
+    }
+
+    /// True if the other coordinate is at most `tiles` tiles away
+    pub fn is_within(&self, other: &Coordinate, tiles: i16) -> bool {
+        let reach = tiles as i64 * TILE_SCALE as i64;
+        self.squared_distance(other) <= reach * reach
+    }
+}
+
+/// Tiles are rounded to the closest thousandth
+impl From<(f32, f32)> for Coordinate {
+    fn from((x, y): (f32, f32)) -> Self {
+        Coordinate::new(
+            (x * TILE_SCALE as f32).round() as i32,
+            (y * TILE_SCALE as f32).round() as i32,
+        )
+    }
+}
+
+impl StableHash for Coordinate {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_i32(self.x);
+        hasher.write_i32(self.y);
+    }
+}
+
+impl Display for Coordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, y) = self.to_tiles();
+        write!(f, "({:.3},{:.3})", x, y)
+    }
+}
+
+/// Straight line distance between two coordinates, in thousandths of a tile
+/// rounded down
+pub fn distance(from: &Coordinate, to: &Coordinate) -> i64 {
+    integer_sqrt(from.squared_distance(to))
+}
+
+fn integer_sqrt(value: i64) -> i64 {
+    if value <= 0 {
+        return 0;
+    }
+    // Newton iterations only on integers
+    let mut x = value;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
 pub trait HasIdentifier {
     fn get_identifier(&self) -> Identifier;
     fn is(&self, identifier: &Identifier) -> bool;
@@ -48,8 +150,10 @@ where
     T: Display + HasIdentifier,
 {
     fn update(&mut self, unit: T) {
-        self.cells
-            .push(Rc::new(RefCell::new(UnitHolder::new(unit, (0.0, 0.0)))));
+        self.cells.push(Rc::new(RefCell::new(UnitHolder::new(
+            unit,
+            Coordinate::default(),
+        ))));
     }
 
     fn update_cell(
@@ -99,6 +203,18 @@ where
     pub fn update(&mut self, coordinate: Coordinate) {
         self.coordinate = coordinate;
     }
+
+    pub fn get_coordinate(&self) -> &Coordinate {
+        &self.coordinate
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.t.as_ref()
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.t.as_mut()
+    }
 }
 
 impl<T> Default for PlayGround<T>
@@ -120,7 +236,7 @@ where
     }
 
     pub fn add_unit(&mut self, content: T) {
-        let holder = UnitHolder::new(content, (0.0, 0.0));
+        let holder = UnitHolder::new(content, Coordinate::default());
         self.cells.push(Rc::new(RefCell::new(holder)))
     }
 
@@ -128,6 +244,21 @@ where
         &self.cells
     }
 
+    pub fn find_cell(&self, identifier: &Identifier) -> Option<Cell<T>> {
+        self.find_cell_by(identifier).map(Rc::clone)
+    }
+
+    /// Take the content out of the map, its cell is dropped
+    pub fn remove_unit(&mut self, identifier: &Identifier) -> Option<T> {
+        let position = self
+            .cells
+            .iter()
+            .position(|cell| cell.borrow().is(identifier))?;
+        let cell = self.cells.remove(position);
+        let mut cell = cell.borrow_mut();
+        cell.t.take()
+    }
+
     fn find_cell_by(&self, identifier: &Identifier) -> Option<&Cell<T>> {
         self.cells.iter().find(|cell| {
             let cell_ptr = Rc::clone(cell);
@@ -162,3 +293,34 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test_coordinate {
+
+    use super::{distance, Coordinate};
+
+    #[test]
+    pub fn should_convert_tiles() {
+        let coordinate = Coordinate::from((2.5, -1.0));
+        assert_eq!(Coordinate::new(2500, -1000), coordinate);
+        assert_eq!((2.5, -1.0), coordinate.to_tiles());
+        assert_eq!(Coordinate::new(3000, 0), Coordinate::from_tiles(3, 0));
+        assert_eq!((3, -1), Coordinate::new(2500, -1500).to_tile());
+    }
+
+    #[test]
+    pub fn should_convert_coordinates_at_the_limits() {
+        let far = Coordinate::new(i32::MAX, i32::MIN);
+        assert_eq!((2_147_484, -2_147_484), far.to_tile());
+        assert_eq!(far, Coordinate::from_tiles(i32::MAX, i32::MIN));
+    }
+
+    #[test]
+    pub fn should_measure_exactly() {
+        let origin = Coordinate::default();
+        let corner = Coordinate::from_tiles(3, 4);
+        assert_eq!(5000, distance(&origin, &corner));
+        assert!(origin.is_within(&corner, 5));
+        assert!(!origin.is_within(&Coordinate::new(3000, 4001), 5));
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::game::{Game, GameStateObserver};
+use crate::components::game_config::GameConfig;
+use crate::components::match_result::MatchResult;
+use crate::components::snapshot::Snapshot;
+use crate::components::state_hash::StateHash;
+use crate::components::turn_strategy::TurnStrategy;
+use crate::entity::game_actions::Action;
+use crate::entity::game_event::{Notification, Turn};
+use crate::entity::player::Player;
+use crate::exceptions::RtsException;
+
+/// Nobody is listening while a replay is played for its snapshots
+struct Silent;
+
+impl GameStateObserver for Silent {
+    fn update(&self, _notification: &Notification) {}
+}
+
+/// A player as it was when the game started
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayPlayer {
+    pub name: String,
+    pub money: i32,
+}
+
+/// Everything needed to play a game again: its rules, its players
+/// and the action requested by each player at each turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: GameConfig,
+    pub players: Vec<ReplayPlayer>,
+    /// One entry per turn, holding one action per player
+    pub turns: Vec<Vec<Action>>,
+    /// Hash of the game state at the end of each turn
+    #[serde(default)]
+    pub hashes: Vec<StateHash>,
+}
+
+/// First turn where playing a replay again did not give the recorded state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Desync {
+    pub turn: Turn,
+    pub expected: StateHash,
+    /// None when the game ended before this turn
+    pub actual: Option<StateHash>,
+}
+
+impl Display for Desync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "Desync at turn {}: expected state {} but got {}",
+                self.turn, self.expected, actual
+            ),
+            None => write!(
+                f,
+                "Desync at turn {}: expected state {} but the game was over",
+                self.turn, self.expected
+            ),
+        }
+    }
+}
+
+impl Replay {
+    pub fn new(config: GameConfig, players: Vec<ReplayPlayer>) -> Self {
+        Replay {
+            config,
+            players,
+            turns: Vec::new(),
+            hashes: Vec::new(),
+        }
+    }
+
+    /// Play the replay again and compare the state of each turn with
+    /// the recorded hashes. Gives the first turn which differs, if any.
+    pub fn verify(&self) -> Result<Option<Desync>, RtsException> {
+        self.play_checked().map(|(_, desync)| desync)
+    }
+
+    /// Play the replay again to compute the outcome of the game,
+    /// fails if the recorded hashes do not match
+    pub fn simulate(&self) -> Result<MatchResult, RtsException> {
+        match self.play_checked()? {
+            (_, Some(desync)) => Err(RtsException::GeneralException(desync.to_string())),
+            (game, None) => Ok(game.match_result()),
+        }
+    }
+
+    /// Play every recorded turn, stopping at the first desync
+    fn play_checked(&self) -> Result<(Game<Silent>, Option<Desync>), RtsException> {
+        let game: Game<Silent> = Game::from_replay(self, Vec::new());
+        let mut is_over = false;
+        for i in 0..self.turns.len().max(self.hashes.len()) {
+            let turn = i as Turn + 1;
+            let expected = self.hashes.get(i);
+            if is_over {
+                let desync = expected.map(|expected| Desync {
+                    turn,
+                    expected: *expected,
+                    actual: None,
+                });
+                return Ok((game, desync));
+            }
+            is_over = game.play_turn()?;
+            let actual = game.state_hash();
+            if let Some(expected) = expected.filter(|expected| **expected != actual) {
+                let desync = Desync {
+                    turn,
+                    expected: *expected,
+                    actual: Some(actual),
+                };
+                return Ok((game, Some(desync)));
+            }
+        }
+        Ok((game, None))
+    }
+
+    /// Play the replay again, keeping the state before the first turn
+    /// and after each recorded turn
+    pub fn snapshots(&self) -> Result<Vec<Snapshot>, RtsException> {
+        let game: Game<Silent> = Game::from_replay(self, Vec::new());
+        let mut snapshots = vec![game.snapshot()];
+        for _ in 0..self.turns.len() {
+            let is_over = game.play_turn()?;
+            snapshots.push(game.snapshot());
+            if is_over {
+                break;
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Players requesting the recorded actions again
+    pub fn to_players(&self) -> Vec<Player<TurnStrategy>> {
+        self.players
+            .iter()
+            .enumerate()
+            .map(|(i, replay_player)| {
+                let actions = self
+                    .turns
+                    .iter()
+                    .map(|turn| turn.get(i).cloned().unwrap_or(Action::Wait))
+                    .collect();
+                let mut player =
+                    Player::new(replay_player.name.clone(), TurnStrategy::scripted(actions));
+                player.update_money(replay_player.money);
+                player
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_replay {
+
+    use crate::components::game::Game;
+    use crate::components::game_config::GameConfig;
+    use crate::components::play_ground::Coordinate;
+    use crate::components::turn_strategy::TurnStrategy;
+    use crate::entity::game_actions::Action;
+    use crate::entity::player::Player;
+    use crate::entity::unit::UnitType;
+
+    use super::{Replay, ReplayPlayer, Silent};
+
+    #[test]
+    pub fn should_read_back_written_replay() {
+        let mut replay = Replay::new(
+            GameConfig::default(),
+            vec![ReplayPlayer {
+                name: "Tigran".to_string(),
+                money: 20,
+            }],
+        );
+        replay.turns.push(vec![Action::BuyUnit(UnitType::Classic)]);
+        replay
+            .turns
+            .push(vec![Action::MoveUnit(1, Coordinate::new(2500, 1000))]);
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let read: Replay = serde_json::from_str(&json).unwrap();
+        assert_eq!(replay, read);
+
+        let players = read.to_players();
+        assert_eq!(&20, players[0].get_money());
+        assert_eq!(
+            Action::BuyUnit(UnitType::Classic),
+            players[0].request().unwrap()
+        );
+    }
+
+    #[test]
+    pub fn should_find_first_turn_which_differs() {
+        let scripted = |name: &str, actions| {
+            let mut player = Player::new(name.to_string(), TurnStrategy::scripted(actions));
+            player.update_money(20);
+            player
+        };
+        let game: Game<Silent> = Game::new(
+            vec![
+                scripted(
+                    "Tigran",
+                    vec![
+                        Action::BuyUnit(UnitType::Classic),
+                        Action::MoveUnit(1, Coordinate::from_tiles(3, 2)),
+                        Action::Wait,
+                    ],
+                ),
+                scripted("Emma", vec![Action::Wait, Action::Wait, Action::EndGame]),
+            ],
+            Vec::new(),
+        );
+        while !game.play_turn().unwrap() {}
+        let replay = game.get_replay();
+        assert_eq!(3, replay.hashes.len());
+        assert_eq!(None, replay.verify().unwrap());
+
+        let mut tampered = replay.clone();
+        tampered.turns[1][0] = Action::MoveUnit(1, Coordinate::from_tiles(4, 2));
+        let desync = tampered.verify().unwrap().unwrap();
+        assert_eq!(2, desync.turn);
+        assert_eq!(replay.hashes[1], desync.expected);
+
+        let result = replay.simulate().unwrap();
+        assert_eq!(vec![0, 0], result.scores);
+        assert!(tampered.simulate().is_err());
+
+        let mut shortened = replay;
+        shortened.turns[1][1] = Action::EndGame;
+        assert_eq!(None, shortened.verify().unwrap().unwrap().actual);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::components::state_hash::{StableHash, StateHasher};
+
+/// Pseudo random numbers for the engine and its rules (SplitMix64).
+/// It is the only source of randomness of a game: two games with the same
+/// seed and the same actions give the same outcome, on any machine.
+/// Only integer arithmetic is used, so results never depend on float rounding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform number in [low, high), low when the range is empty
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        let span = high.wrapping_sub(low) as u64;
+        // Reject the last incomplete span to stay uniform
+        let zone = u64::MAX - (u64::MAX % span);
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return low.wrapping_add((value % span) as i64);
+            }
+        }
+    }
+
+    /// True with the given chance, in percent
+    pub fn chance(&mut self, percent: u8) -> bool {
+        self.range(0, 100) < percent as i64
+    }
+}
+
+impl StableHash for GameRng {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.state)
+    }
+}
+
+#[cfg(test)]
+mod test_rng {
+
+    use super::GameRng;
+
+    #[test]
+    pub fn should_repeat_with_same_seed() {
+        let draw = |seed| {
+            let mut rng = GameRng::new(seed);
+            (0..10).map(|_| rng.next_u64()).collect::<Vec<u64>>()
+        };
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(7));
+    }
+
+    #[test]
+    pub fn should_stay_in_range() {
+        let mut rng = GameRng::new(1);
+        assert!((0..1000).all(|_| (-3..4).contains(&rng.range(-3, 4))));
+        assert_eq!(5, rng.range(5, 5));
+        assert!(!rng.chance(0));
+        assert!(rng.chance(100));
+    }
+
+    #[test]
+    pub fn should_draw_over_the_full_width() {
+        let mut rng = GameRng::new(3);
+        let draws: Vec<i64> = (0..100).map(|_| rng.range(i64::MIN, i64::MAX)).collect();
+        assert!(draws.iter().all(|value| *value < i64::MAX));
+        assert!(draws.iter().any(|value| *value < 0));
+        assert!(draws.iter().any(|value| *value > 0));
+        assert_eq!(i64::MIN, rng.range(i64::MIN, i64::MIN + 1));
+    }
+
+    #[test]
+    pub fn should_give_known_values() {
+        // Values are part of the replay format, they must never change
+        let mut rng = GameRng::new(0);
+        assert_eq!(0xE220_A839_7B1D_CDAF, rng.next_u64());
+        assert_eq!(0x6E78_9E6A_A1B9_65F4, rng.next_u64());
+    }
+}
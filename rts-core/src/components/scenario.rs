@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::Coordinate;
+use crate::entity::game_event::Turn;
+use crate::entity::player::PlayerIndex;
+use crate::entity::unit::UnitType;
+
+/// A unit put on the map by a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioUnit {
+    /// None for a neutral unit
+    pub owner: Option<PlayerIndex>,
+    pub unit_type: UnitType,
+    pub coordinate: Coordinate,
+    /// Where the unit attack-moves once placed, it stands still otherwise
+    #[serde(default)]
+    pub attack_move: Option<Coordinate>,
+}
+
+/// Goal given to the player of a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Objective {
+    /// No unit of the other teams is left, neutral ones aside
+    DestroyEnemies,
+    /// A unit of the player stands within the radius, in tiles
+    Reach { coordinate: Coordinate, radius: i16 },
+    /// The player owns the capture point of the map
+    Capture { point: usize },
+    /// The player scored at least this much
+    Score { points: i32 },
+    /// The player still has units once the turn is reached
+    Survive { turn: Turn },
+}
+
+/// When a trigger fires or a scenario is lost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Condition {
+    /// The turn is reached
+    Turn { turn: Turn },
+    /// A unit of the player stands within the radius, in tiles
+    Reach {
+        player: PlayerIndex,
+        coordinate: Coordinate,
+        radius: i16,
+    },
+    /// The player has this many units or less
+    UnitsLeft { player: PlayerIndex, count: usize },
+}
+
+/// What a trigger does to the game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Effect {
+    /// Put units on the map, around their coordinate when it is taken
+    Spawn {
+        units: Vec<ScenarioUnit>,
+    },
+    GiveMoney {
+        player: PlayerIndex,
+        amount: i32,
+    },
+    /// Text shown to every player, to guide newcomers
+    Message {
+        text: String,
+    },
+}
+
+/// Effects applied once, at the beginning of the first turn the condition holds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub condition: Condition,
+    pub effects: Vec<Effect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioOutcome {
+    InProgress,
+    Succeeded,
+    Failed,
+}
+
+/// A game starting from a prepared situation, with objectives for one player
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Told to the players before the game starts
+    #[serde(default)]
+    pub briefing: String,
+    /// Player who has to meet the objectives
+    #[serde(default)]
+    pub player: PlayerIndex,
+    /// On the map before the first turn
+    #[serde(default)]
+    pub units: Vec<ScenarioUnit>,
+    /// All of them have to be met at the same time
+    #[serde(default)]
+    pub objectives: Vec<Objective>,
+    /// The scenario is lost as soon as one of them holds
+    #[serde(default)]
+    pub defeats: Vec<Condition>,
+    /// Last turn to meet the objectives
+    #[serde(default)]
+    pub turn_limit: Option<Turn>,
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
+}
+
+impl Scenario {
+    /// Outcome at the end of the turn, a scenario without objectives is never won
+    pub fn outcome(&self, turn: Turn, objectives_met: bool, defeated: bool) -> ScenarioOutcome {
+        if !self.objectives.is_empty() && objectives_met {
+            ScenarioOutcome::Succeeded
+        } else if defeated || self.turn_limit.is_some_and(|limit| turn >= limit) {
+            ScenarioOutcome::Failed
+        } else {
+            ScenarioOutcome::InProgress
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_scenario {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{RecordingClientGameState, TestClientGameState};
+    use crate::components::game::Game;
+    use crate::components::game_config::GameConfig;
+    use crate::components::map::Map;
+    use crate::components::play_ground::Coordinate;
+    use crate::components::turn_strategy::TurnStrategy;
+    use crate::entity::game_event::{GameEvent, Turn};
+    use crate::entity::player::Player;
+    use crate::entity::unit::UnitType;
+
+    use super::{Condition, Effect, Objective, Scenario, ScenarioOutcome, ScenarioUnit, Trigger};
+
+    #[test]
+    pub fn should_end_with_objectives_or_turn_limit() {
+        let scenario = Scenario {
+            objectives: vec![Objective::DestroyEnemies],
+            turn_limit: Some(10),
+            ..Scenario::default()
+        };
+        assert_eq!(
+            ScenarioOutcome::InProgress,
+            scenario.outcome(3, false, false)
+        );
+        assert_eq!(
+            ScenarioOutcome::Succeeded,
+            scenario.outcome(10, true, false)
+        );
+        assert_eq!(ScenarioOutcome::Failed, scenario.outcome(10, false, false));
+        assert_eq!(ScenarioOutcome::Failed, scenario.outcome(3, false, true));
+        assert_eq!(
+            ScenarioOutcome::InProgress,
+            Scenario::default().outcome(3, true, false)
+        );
+    }
+
+    fn defense_scenario(turn_limit: Turn) -> GameConfig {
+        let archer = |y| ScenarioUnit {
+            owner: Some(0),
+            unit_type: UnitType::Archer,
+            coordinate: Coordinate::from_tiles(2, y),
+            attack_move: None,
+        };
+        let wave = ScenarioUnit {
+            owner: Some(1),
+            unit_type: UnitType::Classic,
+            coordinate: Coordinate::from_tiles(14, 4),
+            attack_move: Some(Coordinate::from_tiles(2, 4)),
+        };
+        GameConfig {
+            map: Map::new(24, 12),
+            scenario: Some(Scenario {
+                name: "Hold the line".to_string(),
+                units: vec![archer(2), archer(4), archer(6)],
+                objectives: vec![Objective::DestroyEnemies, Objective::Survive { turn: 3 }],
+                defeats: vec![Condition::UnitsLeft {
+                    player: 0,
+                    count: 0,
+                }],
+                turn_limit: Some(turn_limit),
+                triggers: vec![Trigger {
+                    condition: Condition::Turn { turn: 3 },
+                    effects: vec![
+                        Effect::Spawn { units: vec![wave] },
+                        Effect::Message {
+                            text: "Here they come".to_string(),
+                        },
+                    ],
+                }],
+                ..Scenario::default()
+            }),
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    pub fn should_play_scenario_to_its_outcome() {
+        let notifications = Rc::new(RefCell::new(Vec::new()));
+        let players = || {
+            vec![
+                Player::new("Tigran".to_string(), TurnStrategy::scripted(vec![])),
+                Player::new("Wave".to_string(), TurnStrategy::scripted(vec![])),
+            ]
+        };
+        let game = Game::with_config(
+            players(),
+            vec![RecordingClientGameState(Rc::clone(&notifications))],
+            defense_scenario(20),
+        );
+        let snapshot = game.snapshot();
+        assert_eq!(3, snapshot.units.len());
+        assert_eq!(Coordinate::from_tiles(2, 4), snapshot.units[1].coordinate);
+        assert_eq!(Some(ScenarioOutcome::InProgress), game.scenario_outcome());
+
+        let mut turns = 0;
+        while !game.play_turn().unwrap() {
+            turns += 1;
+            assert!(turns < 20);
+        }
+        assert_eq!(Some(ScenarioOutcome::Succeeded), game.scenario_outcome());
+        let events: Vec<GameEvent> = notifications
+            .borrow()
+            .iter()
+            .map(|n| n.get_event().clone())
+            .collect();
+        assert!(events.contains(&GameEvent::ScenarioMessage {
+            text: "Here they come".to_string()
+        }));
+        assert!(events.contains(&GameEvent::ScenarioEnded {
+            outcome: ScenarioOutcome::Succeeded
+        }));
+
+        // The wave is still alive when time is up
+        let game = Game::with_config(players(), vec![TestClientGameState()], defense_scenario(3));
+        for _ in 0..2 {
+            assert!(!game.play_turn().unwrap());
+        }
+        assert!(game.play_turn().unwrap());
+        assert_eq!(Some(ScenarioOutcome::Failed), game.scenario_outcome());
+    }
+}
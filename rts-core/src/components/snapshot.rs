@@ -0,0 +1,40 @@
+use crate::components::map::Map;
+use crate::components::play_ground::{Coordinate, Identifier};
+use crate::entity::game_event::Turn;
+use crate::entity::unit::Unit;
+
+/// A unit and where it stands
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitSnapshot {
+    pub unit: Unit,
+    pub coordinate: Coordinate,
+}
+
+/// A unit hitting another one, with their positions at that time
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackSnapshot {
+    pub attacker: Identifier,
+    pub target: Identifier,
+    pub from: Coordinate,
+    pub to: Coordinate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerSnapshot {
+    pub name: String,
+    pub money: i32,
+    pub units: usize,
+    pub score: i32,
+}
+
+/// Copy of the whole game state at a given turn, used by displayers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub turn: Turn,
+    pub map: Map,
+    /// Sorted by unit identifier
+    pub units: Vec<UnitSnapshot>,
+    pub players: Vec<PlayerSnapshot>,
+    /// Attacks which happened during the turn
+    pub attacks: Vec<AttackSnapshot>,
+}
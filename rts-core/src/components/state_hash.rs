@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Fingerprint of the whole state of a game at the end of a turn.
+/// Written as an hexadecimal string, so JSON readers do not round it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateHash(pub u64);
+
+/// Hash the same state to the same value on every machine and every
+/// compiler version (FNV-1a over little endian bytes)
+pub struct StateHasher {
+    hash: u64,
+}
+
+/// State which takes part in the game state hash
+pub trait StableHash {
+    fn stable_hash(&self, hasher: &mut StateHasher);
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        StateHasher {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_i128(&mut self, value: i128) {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i16(&mut self, value: i16) {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    /// Strings are prefixed by their length so that ("ab", "c") != ("a", "bc")
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write_bytes(value.as_bytes())
+    }
+
+    pub fn write<T: StableHash>(&mut self, value: &T) {
+        value.stable_hash(self)
+    }
+
+    pub fn finish(&self) -> StateHash {
+        StateHash(self.hash)
+    }
+}
+
+impl Display for StateHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl Serialize for StateHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for StateHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        u64::from_str_radix(&hex, 16)
+            .map(StateHash)
+            .map_err(|_| D::Error::custom(format!("Invalid state hash {}", hex)))
+    }
+}
+
+#[cfg(test)]
+mod test_state_hash {
+
+    use super::{StateHash, StateHasher};
+
+    #[test]
+    pub fn should_give_known_values() {
+        // FNV-1a reference values, they must never change
+        assert_eq!(
+            StateHash(0xcbf2_9ce4_8422_2325),
+            StateHasher::new().finish()
+        );
+        let mut hasher = StateHasher::new();
+        hasher.write_bytes(b"a");
+        assert_eq!(StateHash(0xaf63_dc4c_8601_ec8c), hasher.finish());
+    }
+
+    #[test]
+    pub fn should_write_hash_as_hexadecimal() {
+        let hash = StateHash(0xaf63_dc4c_8601_ec8c);
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!("\"af63dc4c8601ec8c\"", json);
+        assert_eq!(hash, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::Write;
+
+use crate::components::map::{BuildingKind, Terrain};
+use crate::components::play_ground::Coordinate;
+use crate::components::snapshot::Snapshot;
+use crate::entity::player::PlayerIndex;
+
+/// Size of a tile of the map, in pixels
+const TILE: f32 = 24.0;
+const UNIT_RADIUS: f32 = 7.0;
+/// Time each turn is shown in an animated replay
+const SECONDS_PER_TURN: f32 = 1.0;
+const PLAYER_COLORS: [&str; 6] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf",
+];
+const NEUTRAL_COLOR: &str = "#7f7f7f";
+
+/// Draw snapshots as SVG images and replays as SVG/HTML animations,
+/// only by generating text
+pub struct SvgRenderer;
+
+impl SvgRenderer {
+    /// A standalone SVG image of a single turn
+    pub fn render_snapshot(snapshot: &Snapshot) -> String {
+        let mut out = Self::open_svg(snapshot);
+        Self::write_frame(&mut out, snapshot);
+        out.push_str("</svg>\n");
+        out
+    }
+
+    /// A standalone SVG image showing each turn one after the other, in a loop
+    pub fn render_replay(frames: &[Snapshot]) -> String {
+        let first = match frames.first() {
+            Some(first) => first,
+            None => return String::new(),
+        };
+        let count = frames.len() as f32;
+        let duration = count * SECONDS_PER_TURN;
+        let mut out = Self::open_svg(first);
+        // Writing into a String never fails
+        let _ = writeln!(
+            out,
+            "<style>.frame {{ opacity: 0; animation: turn {:.1}s step-end infinite; }} \
+             @keyframes turn {{ 0% {{ opacity: 1; }} {:.3}% {{ opacity: 0; }} 100% {{ opacity: 0; }} }}</style>",
+            duration,
+            100.0 / count
+        );
+        for (i, frame) in frames.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "<g class=\"frame\" style=\"animation-delay: {:.1}s\">",
+                i as f32 * SECONDS_PER_TURN
+            );
+            Self::write_frame(&mut out, frame);
+            out.push_str("</g>\n");
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    /// An HTML page embedding the animated replay, for match pages
+    pub fn render_replay_html(title: &str, frames: &[Snapshot]) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n\
+             <body>\n<h1>{title}</h1>\n{svg}</body>\n</html>\n",
+            title = escape(title),
+            svg = Self::render_replay(frames)
+        )
+    }
+
+    fn open_svg(snapshot: &Snapshot) -> String {
+        let width = *snapshot.map.get_width() as f32 * TILE;
+        let height = (*snapshot.map.get_height() as f32 + 1.0) * TILE;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" \
+             viewBox=\"0 0 {w:.0} {h:.0}\" font-family=\"monospace\" font-size=\"12\">\n",
+            w = width,
+            h = height
+        )
+    }
+
+    fn write_frame(out: &mut String, snapshot: &Snapshot) {
+        let map = &snapshot.map;
+        for y in 0..*map.get_height() {
+            for x in 0..*map.get_width() {
+                let terrain = map.get_terrain(x, y).unwrap_or(&Terrain::Plain);
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{:.0}\" y=\"{:.0}\" width=\"{:.0}\" height=\"{:.0}\" fill=\"{}\"/>",
+                    x as f32 * TILE,
+                    y as f32 * TILE,
+                    TILE,
+                    TILE,
+                    terrain_color(terrain)
+                );
+            }
+        }
+
+        for building in map.get_buildings() {
+            let (x, y) = to_pixels(&building.coordinate);
+            let label = match building.kind {
+                BuildingKind::Barrack => "B",
+                BuildingKind::Bank => "$",
+            };
+            let _ = writeln!(
+                out,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.0}\" height=\"{:.0}\" fill=\"{}\" stroke=\"black\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"white\">{}</text>",
+                x - TILE / 2.0,
+                y - TILE / 2.0,
+                TILE,
+                TILE,
+                owner_color(&Some(building.owner)),
+                x,
+                y + 4.0,
+                label
+            );
+        }
+
+        for placed in &snapshot.units {
+            let (x, y) = to_pixels(&placed.coordinate);
+            let unit = &placed.unit;
+            let color = owner_color(unit.get_owner());
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"none\" stroke=\"{}\" \
+                 stroke-opacity=\"0.3\" stroke-dasharray=\"4 4\"/>",
+                x,
+                y,
+                *unit.get_range() as f32 * TILE,
+                color
+            );
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\" stroke=\"black\"/>",
+                x, y, UNIT_RADIUS, color
+            );
+            let ratio = (*unit.get_health() as f32 / (*unit.get_max_health()).max(1) as f32)
+                .clamp(0.0, 1.0);
+            let bar_width = UNIT_RADIUS * 2.0;
+            let _ = writeln!(
+                out,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"3\" fill=\"#400\"/>\
+                 <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"3\" fill=\"#0c0\"/>",
+                x - UNIT_RADIUS,
+                y - UNIT_RADIUS - 5.0,
+                bar_width,
+                x - UNIT_RADIUS,
+                y - UNIT_RADIUS - 5.0,
+                bar_width * ratio
+            );
+        }
+
+        for attack in &snapshot.attacks {
+            let (x1, y1) = to_pixels(&attack.from);
+            let (x2, y2) = to_pixels(&attack.to);
+            let _ = writeln!(
+                out,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"red\" stroke-width=\"2\"/>",
+                x1, y1, x2, y2
+            );
+        }
+
+        let status: Vec<String> = snapshot
+            .players
+            .iter()
+            .map(|player| {
+                format!(
+                    "{} {}$ {}u {}pts",
+                    escape(&player.name),
+                    player.money,
+                    player.units,
+                    player.score
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "<text x=\"4\" y=\"{:.1}\">Turn {} | {}</text>",
+            (*map.get_height() as f32 + 0.7) * TILE,
+            snapshot.turn,
+            status.join(" | ")
+        );
+    }
+}
+
+/// Center of the coordinate, in pixels
+fn to_pixels(coordinate: &Coordinate) -> (f32, f32) {
+    let (x, y) = coordinate.to_tiles();
+    (x * TILE + TILE / 2.0, y * TILE + TILE / 2.0)
+}
+
+fn terrain_color(terrain: &Terrain) -> &'static str {
+    match terrain {
+        Terrain::Plain => "#c8e6a0",
+        Terrain::Forest => "#3c7a3c",
+        Terrain::Water => "#5fa8e0",
+        Terrain::Wall => "#555555",
+        Terrain::HighGround => "#c2a878",
+    }
+}
+
+fn owner_color(owner: &Option<PlayerIndex>) -> &'static str {
+    match owner {
+        Some(index) => PLAYER_COLORS[index % PLAYER_COLORS.len()],
+        None => NEUTRAL_COLOR,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test_svg_renderer {
+
+    use crate::components::map::Map;
+    use crate::components::play_ground::Coordinate;
+    use crate::components::snapshot::{AttackSnapshot, PlayerSnapshot, Snapshot, UnitSnapshot};
+    use crate::entity::unit::Unit;
+
+    use super::SvgRenderer;
+
+    fn snapshot(turn: u32) -> Snapshot {
+        let mut unit = Unit::from(1, 20, 10, 5, 2, Vec::new());
+        unit.set_owner(0);
+        Snapshot {
+            turn,
+            map: Map::new(3, 2),
+            units: vec![UnitSnapshot {
+                unit,
+                coordinate: Coordinate::from_tiles(1, 1),
+            }],
+            players: vec![PlayerSnapshot {
+                name: "<Tigran>".to_string(),
+                money: 80,
+                units: 1,
+                score: 0,
+            }],
+            attacks: vec![AttackSnapshot {
+                attacker: 1,
+                target: 2,
+                from: Coordinate::from_tiles(1, 1),
+                to: Coordinate::from_tiles(2, 0),
+            }],
+        }
+    }
+
+    #[test]
+    pub fn should_draw_units_and_attacks() {
+        let svg = SvgRenderer::render_snapshot(&snapshot(1));
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(6, svg.matches("fill=\"#c8e6a0\"").count());
+        assert!(svg.contains("<circle cx=\"36.0\" cy=\"36.0\" r=\"48.0\""));
+        assert!(svg.contains("<line x1=\"36.0\" y1=\"36.0\" x2=\"60.0\" y2=\"12.0\""));
+        assert!(svg.contains("&lt;Tigran&gt;"));
+        assert_eq!(svg, SvgRenderer::render_snapshot(&snapshot(1)));
+    }
+
+    #[test]
+    pub fn should_animate_every_turn() {
+        let html = SvgRenderer::render_replay_html("Match", &[snapshot(0), snapshot(1)]);
+
+        assert_eq!(2, html.matches("<g class=\"frame\"").count());
+        assert!(html.contains("animation-delay: 1.0s"));
+        assert!(html.contains("<h1>Match</h1>"));
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::state_hash::{StableHash, StateHasher};
+use crate::entity::unit::{StatGrowth, UnitType};
+use crate::exceptions::RtsException;
+
+/// Name of a technology in the tech tree
+pub type TechnologyId = String;
+
+/// What a researched technology gives to a player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "upgrade")]
+pub enum Upgrade {
+    /// Added to every unit of the player, those alive and those to come
+    Stats { growth: StatGrowth },
+    /// Units are produced for less money, in percent of their cost
+    Production { percent: i16 },
+    /// Money added to each batch given by the bank
+    Income { amount: i32 },
+    /// The unit type can only be bought once researched
+    Unlock { unit_type: UnitType },
+}
+
+/// An upgrade players can research once they know its prerequisites
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Technology {
+    pub cost: i32,
+    /// Number of turns before the research is done
+    pub turns: u32,
+    #[serde(default)]
+    pub requires: Vec<TechnologyId>,
+    pub upgrades: Vec<Upgrade>,
+}
+
+/// Every technology which can be researched during a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TechTree {
+    pub technologies: BTreeMap<TechnologyId, Technology>,
+}
+
+/// Sum of the upgrades researched by a player
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Upgrades {
+    pub growth: StatGrowth,
+    /// Discount on units, in percent
+    pub production: i16,
+    pub income: i32,
+    pub unlocked: BTreeSet<UnitType>,
+}
+
+/// A research a player is waiting for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResearchProgress {
+    pub technology: TechnologyId,
+    pub turns_left: u32,
+}
+
+/// Technologies of a player: researched and in progress
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ResearchState {
+    /// In the order they were researched
+    completed: Vec<TechnologyId>,
+    current: Option<ResearchProgress>,
+    upgrades: Upgrades,
+}
+
+impl Upgrades {
+    fn add(&mut self, upgrade: &Upgrade) {
+        match upgrade {
+            Upgrade::Stats { growth } => {
+                self.growth.health = self.growth.health.saturating_add(growth.health);
+                self.growth.mana = self.growth.mana.saturating_add(growth.mana);
+                self.growth.armor = self.growth.armor.saturating_add(growth.armor);
+                self.growth.range = self.growth.range.saturating_add(growth.range);
+            }
+            Upgrade::Production { percent } => {
+                self.production = self.production.saturating_add(*percent).clamp(0, 100)
+            }
+            Upgrade::Income { amount } => self.income = self.income.saturating_add(*amount),
+            Upgrade::Unlock { unit_type } => {
+                self.unlocked.insert(*unit_type);
+            }
+        }
+    }
+
+    /// Cost of a unit once the production discount is applied
+    pub fn get_unit_cost(&self, cost: i16) -> i16 {
+        (cost as i32 * (100 - self.production as i32) / 100) as i16
+    }
+}
+
+impl ResearchState {
+    pub fn get_completed(&self) -> &[TechnologyId] {
+        &self.completed
+    }
+
+    pub fn get_current(&self) -> &Option<ResearchProgress> {
+        &self.current
+    }
+
+    pub fn get_upgrades(&self) -> &Upgrades {
+        &self.upgrades
+    }
+
+    pub fn has_completed(&self, technology: &str) -> bool {
+        self.completed.iter().any(|done| done == technology)
+    }
+
+    /// Begin to research a technology, one at a time
+    pub fn start(&mut self, tree: &TechTree, technology: &str) -> Result<(), RtsException> {
+        let turns = tree.check_can_start(self, technology)?.turns;
+        self.current = Some(ResearchProgress {
+            technology: technology.to_string(),
+            turns_left: turns,
+        });
+        Ok(())
+    }
+
+    /// Make one turn pass for the current research.
+    /// Returns the technology researched when it is done.
+    pub fn progress(&mut self, tree: &TechTree) -> Option<TechnologyId> {
+        let progress = self.current.as_mut()?;
+        progress.turns_left = progress.turns_left.saturating_sub(1);
+        if progress.turns_left > 0 {
+            return None;
+        }
+        let technology = self.current.take()?.technology;
+        if let Ok(researched) = tree.get(&technology) {
+            researched
+                .upgrades
+                .iter()
+                .for_each(|upgrade| self.upgrades.add(upgrade));
+        }
+        self.completed.push(technology.clone());
+        Some(technology)
+    }
+}
+
+impl StableHash for ResearchState {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_u64(self.completed.len() as u64);
+        self.completed
+            .iter()
+            .for_each(|technology| hasher.write_str(technology));
+        match &self.current {
+            Some(progress) => {
+                hasher.write_str(&progress.technology);
+                hasher.write_u64(progress.turns_left as u64);
+            }
+            None => hasher.write_str(""),
+        }
+    }
+}
+
+impl Default for TechTree {
+    fn default() -> Self {
+        let mut technologies = BTreeMap::new();
+        let armor = |amount| Upgrade::Stats {
+            growth: StatGrowth {
+                armor: amount,
+                ..StatGrowth::default()
+            },
+        };
+        technologies.insert(
+            "reinforced_armor".to_string(),
+            Technology {
+                cost: 60,
+                turns: 2,
+                requires: Vec::new(),
+                upgrades: vec![armor(1)],
+            },
+        );
+        technologies.insert(
+            "heavy_plating".to_string(),
+            Technology {
+                cost: 120,
+                turns: 3,
+                requires: vec!["reinforced_armor".to_string()],
+                upgrades: vec![armor(2)],
+            },
+        );
+        technologies.insert(
+            "drill_sergeants".to_string(),
+            Technology {
+                cost: 80,
+                turns: 2,
+                requires: Vec::new(),
+                upgrades: vec![Upgrade::Production { percent: 20 }],
+            },
+        );
+        technologies.insert(
+            "trade_routes".to_string(),
+            Technology {
+                cost: 100,
+                turns: 3,
+                requires: Vec::new(),
+                upgrades: vec![Upgrade::Income { amount: 25 }],
+            },
+        );
+        technologies.insert(
+            "arcane_studies".to_string(),
+            Technology {
+                cost: 150,
+                turns: 4,
+                requires: vec!["trade_routes".to_string()],
+                upgrades: vec![Upgrade::Unlock {
+                    unit_type: UnitType::Mage,
+                }],
+            },
+        );
+        TechTree { technologies }
+    }
+}
+
+impl TechTree {
+    pub fn get(&self, technology: &str) -> Result<&Technology, RtsException> {
+        self.technologies.get(technology).ok_or_else(|| {
+            RtsException::ExecuteActionException(format!("Unknown technology {}", technology))
+        })
+    }
+
+    /// Check the player can research the technology now and give it back
+    pub fn check_can_start(
+        &self,
+        state: &ResearchState,
+        technology: &str,
+    ) -> Result<&Technology, RtsException> {
+        let researched = self.get(technology)?;
+        if let Some(progress) = &state.current {
+            return Err(RtsException::ExecuteActionException(format!(
+                "Already researching {}",
+                progress.technology
+            )));
+        }
+        if state.has_completed(technology) {
+            return Err(RtsException::ExecuteActionException(format!(
+                "{} is already researched",
+                technology
+            )));
+        }
+        if let Some(missing) = researched
+            .requires
+            .iter()
+            .find(|required| !state.has_completed(required))
+        {
+            return Err(RtsException::ExecuteActionException(format!(
+                "{} needs {} first",
+                technology, missing
+            )));
+        }
+        Ok(researched)
+    }
+
+    /// Unit types which have to be unlocked by a technology before being bought
+    pub fn get_locked_units(&self) -> BTreeSet<UnitType> {
+        self.technologies
+            .values()
+            .flat_map(|technology| technology.upgrades.iter())
+            .filter_map(|upgrade| match upgrade {
+                Upgrade::Unlock { unit_type } => Some(*unit_type),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Check prerequisites exist and no technology requires itself,
+    /// even through other ones
+    pub fn validate(&self) -> Result<(), RtsException> {
+        for (name, technology) in &self.technologies {
+            if let Some(missing) = technology
+                .requires
+                .iter()
+                .find(|required| !self.technologies.contains_key(*required))
+            {
+                return Err(RtsException::GeneralException(format!(
+                    "Technology {} requires unknown {}",
+                    name, missing
+                )));
+            }
+        }
+        // Technologies are taken in the order they can be researched,
+        // the ones left at the end wait for each other
+        let mut known: BTreeSet<&TechnologyId> = BTreeSet::new();
+        while known.len() < self.technologies.len() {
+            let next: Vec<&TechnologyId> = self
+                .technologies
+                .iter()
+                .filter(|(name, technology)| {
+                    !known.contains(name)
+                        && technology
+                            .requires
+                            .iter()
+                            .all(|required| known.contains(required))
+                })
+                .map(|(name, _)| name)
+                .collect();
+            if next.is_empty() {
+                return Err(RtsException::GeneralException(
+                    "Technologies require each other in a cycle".to_string(),
+                ));
+            }
+            known.extend(next);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_tech_tree {
+
+    use crate::entity::unit::UnitType;
+
+    use super::{ResearchState, TechTree, Technology};
+
+    #[test]
+    pub fn should_research_after_prerequisites() {
+        let tree = TechTree::default();
+        let mut state = ResearchState::default();
+        assert!(state.start(&tree, "arcane_studies").is_err());
+        assert!(state.start(&tree, "time_travel").is_err());
+
+        state.start(&tree, "trade_routes").unwrap();
+        assert!(state.start(&tree, "drill_sergeants").is_err());
+        assert_eq!(None, state.progress(&tree));
+        assert_eq!(None, state.progress(&tree));
+        assert_eq!(Some("trade_routes".to_string()), state.progress(&tree));
+        assert_eq!(25, state.get_upgrades().income);
+
+        state.start(&tree, "arcane_studies").unwrap();
+        while state.progress(&tree).is_none() {}
+        assert!(state.get_upgrades().unlocked.contains(&UnitType::Mage));
+        assert!(state.start(&tree, "trade_routes").is_err());
+    }
+
+    #[test]
+    pub fn should_find_cycles() {
+        let mut tree = TechTree::default();
+        assert!(tree.validate().is_ok());
+        assert!(tree.get_locked_units().contains(&UnitType::Mage));
+
+        let requiring = |required: &str| Technology {
+            cost: 0,
+            turns: 1,
+            requires: vec![required.to_string()],
+            upgrades: Vec::new(),
+        };
+        tree.technologies
+            .insert("chicken".to_string(), requiring("egg"));
+        assert!(tree.validate().is_err());
+        tree.technologies
+            .insert("egg".to_string(), requiring("chicken"));
+        assert!(tree.validate().is_err());
+    }
+}
@@ -1,15 +1,71 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
 use crate::entity::game_actions::Action;
 use crate::entity::player::TurnStrategyRequester;
+use crate::entity::unit::UnitType;
 use crate::exceptions::RtsException;
 
+/// Actions of the build order, one per turn, starting over once done
+const BUILD_ORDER: [Action; 4] = [
+    Action::GiveMoneyBatch,
+    Action::BuyUnit(UnitType::Classic),
+    Action::GiveMoneyBatch,
+    Action::BuyUnit(UnitType::Archer),
+];
+
 pub enum TurnStrategy {
     AI,
+    /// Collect money and buy units, without looking at the map.
+    /// Holds the position in the build order.
+    BuildOrder(Cell<usize>),
+    /// Play the given actions one per turn, then wait
+    Scripted(RefCell<VecDeque<Action>>),
+}
+
+impl TurnStrategy {
+    pub fn build_order() -> Self {
+        TurnStrategy::BuildOrder(Cell::new(0))
+    }
+
+    pub fn scripted(actions: Vec<Action>) -> Self {
+        TurnStrategy::Scripted(RefCell::new(actions.into()))
+    }
 }
 
 impl TurnStrategyRequester for TurnStrategy {
     fn request(&self) -> Result<Action, RtsException> {
         match &self {
             TurnStrategy::AI => todo!(),
+            TurnStrategy::BuildOrder(step) => {
+                let action = BUILD_ORDER[step.get() % BUILD_ORDER.len()].clone();
+                step.set(step.get() + 1);
+                Ok(action)
+            }
+            TurnStrategy::Scripted(actions) => {
+                Ok(actions.borrow_mut().pop_front().unwrap_or(Action::Wait))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod test_turn_strategy {
+
+    use crate::entity::game_actions::Action;
+    use crate::entity::player::TurnStrategyRequester;
+    use crate::entity::unit::UnitType;
+
+    use super::{TurnStrategy, BUILD_ORDER};
+
+    #[test]
+    pub fn should_loop_over_the_build_order() {
+        let strategy = TurnStrategy::build_order();
+        let played: Vec<Action> = (0..BUILD_ORDER.len() + 2)
+            .map(|_| strategy.request().unwrap())
+            .collect();
+        assert_eq!(Action::GiveMoneyBatch, played[0]);
+        assert_eq!(Action::BuyUnit(UnitType::Classic), played[1]);
+        assert_eq!(played[..2], played[BUILD_ORDER.len()..]);
+    }
+}
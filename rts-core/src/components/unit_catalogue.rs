@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::Identifier;
+use crate::entity::skill::{DamageType, Skill, SkillKind, SkillShape, SkillUpgrade};
+use crate::entity::status_effect::StatusEffect;
+use crate::entity::unit::{ArmorClass, StatGrowth, Unit, UnitType};
+use crate::exceptions::RtsException;
+
+/// Damage dealt when nothing is said for a damage type against an armor class, in percent
+const FULL_EFFECTIVENESS: i16 = 100;
+const EXPERIENCE_PER_DAMAGE: u32 = 1;
+const KILL_EXPERIENCE_PERCENT: u32 = 100;
+
+/// Description of a skill given to every new unit of a type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkillSpec {
+    pub mana_cost: i8,
+    pub damage: i16,
+    /// In ms
+    pub cooldown: i32,
+    pub damage_type: DamageType,
+    pub shape: SkillShape,
+    pub kind: SkillKind,
+    pub effect: Option<StatusEffect>,
+}
+
+/// How a unit without orders picks the enemy it attacks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetPolicy {
+    #[default]
+    Nearest,
+    /// Least health left
+    Weakest,
+    /// Strongest attack
+    HighestThreat,
+}
+
+/// Stats of a unit type, given to every unit built
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitSpec {
+    pub cost: i16,
+    pub max_health: i16,
+    pub max_mana: i16,
+    pub max_armor: i16,
+    pub max_range: i16,
+    pub armor_class: ArmorClass,
+    /// How far the unit sees through the fog of war, in tiles
+    pub vision: i16,
+    /// Tiles walked per turn
+    pub speed: i16,
+    /// Footprint, in thousandths of a tile
+    pub radius: i32,
+    pub skills: Vec<SkillSpec>,
+    /// Ranks the unit can reach, in order
+    #[serde(default)]
+    pub levels: Vec<LevelSpec>,
+    #[serde(default)]
+    pub targeting: TargetPolicy,
+}
+
+/// A rank a unit reaches with enough experience
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelSpec {
+    /// Total experience needed
+    pub experience: u32,
+    #[serde(default)]
+    pub growth: StatGrowth,
+    /// Applied to every skill of the unit
+    #[serde(default)]
+    pub skills: SkillUpgrade,
+}
+
+/// How units earn experience
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExperienceRules {
+    /// For each point of health taken from an enemy
+    pub per_damage: u32,
+    /// Percent of the cost of an enemy killed
+    pub kill_percent: u32,
+}
+
+/// Percent of the damage dealt by each damage type to each armor class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectivenessMatrix(BTreeMap<DamageType, BTreeMap<ArmorClass, i16>>);
+
+/// Every unit type which can be built, and how well they fight each other
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitCatalogue {
+    pub units: BTreeMap<UnitType, UnitSpec>,
+    pub effectiveness: EffectivenessMatrix,
+    #[serde(default)]
+    pub experience: ExperienceRules,
+}
+
+impl SkillSpec {
+    /// A single target physical attack
+    pub fn attack(damage: i16) -> Self {
+        SkillSpec {
+            mana_cost: 0,
+            damage,
+            cooldown: 0,
+            damage_type: DamageType::Physical,
+            shape: SkillShape::Single,
+            kind: SkillKind::Damage,
+            effect: None,
+        }
+    }
+
+    pub fn build(&self) -> Skill {
+        let skill = Skill::from(self.mana_cost, self.damage, self.cooldown)
+            .with_damage_type(self.damage_type)
+            .with_shape(self.shape)
+            .with_kind(self.kind);
+        match self.effect {
+            Some(effect) => skill.with_effect(effect),
+            None => skill,
+        }
+    }
+}
+
+impl EffectivenessMatrix {
+    /// Change the percent of damage dealt by a damage type to an armor class
+    pub fn with(mut self, damage_type: DamageType, armor_class: ArmorClass, percent: i16) -> Self {
+        self.0
+            .entry(damage_type)
+            .or_default()
+            .insert(armor_class, percent);
+        self
+    }
+
+    /// Percent of the damage dealt, full damage when not given
+    pub fn get(&self, damage_type: &DamageType, armor_class: &ArmorClass) -> i16 {
+        self.0
+            .get(damage_type)
+            .and_then(|row| row.get(armor_class))
+            .copied()
+            .unwrap_or(FULL_EFFECTIVENESS)
+    }
+
+    /// Damage left once the armor class resisted it, never negative
+    pub fn apply(&self, damage: i16, damage_type: &DamageType, armor_class: &ArmorClass) -> i16 {
+        let percent = self.get(damage_type, armor_class).max(0) as i32;
+        (damage.max(0) as i32 * percent / 100).min(i16::MAX as i32) as i16
+    }
+}
+
+impl Default for EffectivenessMatrix {
+    fn default() -> Self {
+        EffectivenessMatrix(BTreeMap::new())
+            .with(DamageType::Physical, ArmorClass::Heavy, 70)
+            .with(DamageType::Physical, ArmorClass::Fortified, 50)
+            .with(DamageType::Piercing, ArmorClass::Light, 150)
+            .with(DamageType::Piercing, ArmorClass::Heavy, 50)
+            .with(DamageType::Piercing, ArmorClass::Fortified, 35)
+            .with(DamageType::Magic, ArmorClass::Heavy, 150)
+            .with(DamageType::Magic, ArmorClass::Fortified, 75)
+    }
+}
+
+impl Default for ExperienceRules {
+    fn default() -> Self {
+        ExperienceRules {
+            per_damage: EXPERIENCE_PER_DAMAGE,
+            kill_percent: KILL_EXPERIENCE_PERCENT,
+        }
+    }
+}
+
+impl TargetPolicy {
+    /// Rank of an enemy for the policy, the lowest is attacked first
+    /// and ties go to the closest
+    pub fn priority(&self, target: &Unit) -> i64 {
+        match self {
+            TargetPolicy::Nearest => 0,
+            TargetPolicy::Weakest => *target.get_health() as i64,
+            TargetPolicy::HighestThreat => -(target.get_threat() as i64),
+        }
+    }
+}
+
+impl LevelSpec {
+    /// Ranks of the default catalogue, shared by every unit type
+    fn veteran_levels() -> Vec<LevelSpec> {
+        [20, 50, 100]
+            .into_iter()
+            .map(|experience| LevelSpec {
+                experience,
+                growth: StatGrowth {
+                    health: 5,
+                    armor: 1,
+                    ..StatGrowth::default()
+                },
+                skills: SkillUpgrade {
+                    damage: 2,
+                    cooldown_reduction: 500,
+                    mana_cost_reduction: 1,
+                },
+            })
+            .collect()
+    }
+}
+
+impl Default for UnitCatalogue {
+    fn default() -> Self {
+        let mut units = BTreeMap::new();
+        units.insert(
+            UnitType::Classic,
+            UnitSpec {
+                cost: 20,
+                max_health: 20,
+                max_mana: 10,
+                max_armor: 5,
+                max_range: 15,
+                armor_class: ArmorClass::Light,
+                vision: 8,
+                speed: 3,
+                radius: 300,
+                skills: vec![SkillSpec::attack(10)],
+                levels: LevelSpec::veteran_levels(),
+                targeting: TargetPolicy::Nearest,
+            },
+        );
+        units.insert(
+            UnitType::Archer,
+            UnitSpec {
+                cost: 25,
+                max_health: 15,
+                max_mana: 10,
+                max_armor: 2,
+                max_range: 20,
+                armor_class: ArmorClass::Light,
+                vision: 10,
+                speed: 3,
+                radius: 250,
+                skills: vec![SkillSpec {
+                    damage_type: DamageType::Piercing,
+                    ..SkillSpec::attack(8)
+                }],
+                levels: LevelSpec::veteran_levels(),
+                targeting: TargetPolicy::Weakest,
+            },
+        );
+        units.insert(
+            UnitType::Knight,
+            UnitSpec {
+                cost: 35,
+                max_health: 35,
+                max_mana: 0,
+                max_armor: 8,
+                max_range: 1,
+                armor_class: ArmorClass::Heavy,
+                vision: 6,
+                speed: 2,
+                radius: 400,
+                skills: vec![SkillSpec::attack(12)],
+                levels: LevelSpec::veteran_levels(),
+                targeting: TargetPolicy::Nearest,
+            },
+        );
+        units.insert(
+            UnitType::Mage,
+            UnitSpec {
+                cost: 30,
+                max_health: 12,
+                max_mana: 30,
+                max_armor: 1,
+                max_range: 12,
+                armor_class: ArmorClass::Light,
+                vision: 8,
+                speed: 2,
+                radius: 250,
+                skills: vec![SkillSpec {
+                    mana_cost: 5,
+                    cooldown: 2000,
+                    damage_type: DamageType::Magic,
+                    ..SkillSpec::attack(14)
+                }],
+                levels: LevelSpec::veteran_levels(),
+                targeting: TargetPolicy::HighestThreat,
+            },
+        );
+        UnitCatalogue {
+            units,
+            effectiveness: EffectivenessMatrix::default(),
+            experience: ExperienceRules::default(),
+        }
+    }
+}
+
+impl UnitCatalogue {
+    pub fn get(&self, unit_type: &UnitType) -> Result<&UnitSpec, RtsException> {
+        self.units.get(unit_type).ok_or_else(|| {
+            RtsException::GeneralException(format!("No {} unit in the catalogue", unit_type))
+        })
+    }
+
+    /// Price of a unit, also the score given for killing it
+    pub fn get_cost(&self, unit_type: &UnitType) -> Result<i16, RtsException> {
+        self.get(unit_type).map(|spec| spec.cost)
+    }
+
+    /// How far a unit sees through the fog of war, in tiles. Unknown types are blind.
+    pub fn get_vision(&self, unit_type: &UnitType) -> i16 {
+        self.get(unit_type).map(|spec| spec.vision).unwrap_or(0)
+    }
+
+    /// How units of the type pick their targets, nearest for unknown types
+    pub fn get_targeting(&self, unit_type: &UnitType) -> TargetPolicy {
+        self.get(unit_type)
+            .map(|spec| spec.targeting)
+            .unwrap_or_default()
+    }
+
+    pub fn get_effectiveness(&self) -> &EffectivenessMatrix {
+        &self.effectiveness
+    }
+
+    /// Experience earned for taking health from enemies
+    pub fn get_damage_experience(&self, damage: i16) -> u32 {
+        (damage.max(0) as u32).saturating_mul(self.experience.per_damage)
+    }
+
+    /// Experience earned for killing an enemy unit of the type
+    pub fn get_kill_experience(&self, unit_type: &UnitType) -> u32 {
+        let cost = self.get_cost(unit_type).unwrap_or(0).max(0) as u32;
+        cost.saturating_mul(self.experience.kill_percent) / 100
+    }
+
+    /// Rank up the unit as long as it has the experience of its next level.
+    /// Returns the number of ranks gained.
+    pub fn promote(&self, unit: &mut Unit) -> u8 {
+        let levels = match self.get(unit.get_unit_type()) {
+            Ok(spec) => &spec.levels,
+            Err(_) => return 0,
+        };
+        let mut gained = 0;
+        while let Some(level) = levels.get(*unit.get_rank() as usize) {
+            if *unit.get_experience() < level.experience {
+                break;
+            }
+            unit.promote(&level.growth, &level.skills);
+            gained += 1;
+        }
+        gained
+    }
+
+    /// Create a unit of the type, with its stats at the top
+    pub fn build(&self, identifier: Identifier, unit_type: UnitType) -> Result<Unit, RtsException> {
+        let spec = self.get(&unit_type)?;
+        Ok(Unit::from(
+            identifier,
+            spec.max_health,
+            spec.max_mana,
+            spec.max_armor,
+            spec.max_range,
+            spec.skills.iter().map(SkillSpec::build).collect(),
+        )
+        .with_unit_type(unit_type)
+        .with_armor_class(spec.armor_class)
+        .with_speed(spec.speed)
+        .with_radius(spec.radius))
+    }
+}
+
+#[cfg(test)]
+mod test_unit_catalogue {
+
+    use crate::entity::skill::DamageType;
+    use crate::entity::unit::{ArmorClass, UnitType};
+
+    use super::{EffectivenessMatrix, UnitCatalogue};
+
+    #[test]
+    pub fn should_rank_up_with_experience() {
+        let catalogue = UnitCatalogue::default();
+        let mut archer = catalogue.build(1, UnitType::Archer).unwrap();
+        archer.gain_experience(catalogue.get_damage_experience(15));
+        assert_eq!(0, catalogue.promote(&mut archer));
+
+        archer.gain_experience(catalogue.get_kill_experience(&UnitType::Knight));
+        assert_eq!(2, catalogue.promote(&mut archer));
+        assert_eq!(&2, archer.get_rank());
+        assert_eq!(&25, archer.get_health());
+        assert_eq!(&4, archer.get_armor());
+        assert_eq!(&12, archer.get_skills()[0].get_damage());
+        assert_eq!(&2, archer.get_skills()[0].get_level());
+
+        archer.gain_experience(1000);
+        assert_eq!(1, catalogue.promote(&mut archer));
+        assert_eq!(0, catalogue.promote(&mut archer));
+    }
+
+    #[test]
+    pub fn should_resist_by_armor_class() {
+        let matrix = EffectivenessMatrix::default();
+        assert_eq!(
+            10,
+            matrix.apply(10, &DamageType::Physical, &ArmorClass::Light)
+        );
+        assert_eq!(
+            15,
+            matrix.apply(10, &DamageType::Piercing, &ArmorClass::Light)
+        );
+        assert_eq!(
+            5,
+            matrix.apply(10, &DamageType::Piercing, &ArmorClass::Heavy)
+        );
+        assert_eq!(15, matrix.apply(10, &DamageType::Magic, &ArmorClass::Heavy));
+        assert_eq!(0, matrix.apply(-4, &DamageType::Magic, &ArmorClass::Heavy));
+
+        let matrix = matrix.with(DamageType::Magic, ArmorClass::Heavy, 0);
+        assert_eq!(0, matrix.apply(10, &DamageType::Magic, &ArmorClass::Heavy));
+    }
+
+    #[test]
+    pub fn should_build_units_from_their_spec() {
+        let catalogue = UnitCatalogue::default();
+        let knight = catalogue.build(3, UnitType::Knight).unwrap();
+        assert_eq!(&UnitType::Knight, knight.get_unit_type());
+        assert_eq!(&ArmorClass::Heavy, knight.get_armor_class());
+        assert_eq!(&35, knight.get_health());
+
+        let mage = catalogue.build(4, UnitType::Mage).unwrap();
+        assert_eq!(&DamageType::Magic, mage.get_skills()[0].get_damage_type());
+
+        let mut catalogue = catalogue;
+        catalogue.units.remove(&UnitType::Archer);
+        assert!(catalogue.build(5, UnitType::Archer).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::components::tech_tree::Upgrades;
+use crate::components::unit_catalogue::UnitCatalogue;
 use crate::entity::unit::{Unit, UnitType};
 use crate::exceptions::RtsException;
 
@@ -10,6 +12,7 @@ type InnerIdentifier = Rc<RefCell<Identifier>>;
 
 pub struct UnitFactory {
     counter: Counter,
+    catalogue: UnitCatalogue,
 }
 
 pub struct Counter {
@@ -18,21 +21,34 @@ pub struct Counter {
 
 impl Default for UnitFactory {
     fn default() -> Self {
-        Self::new()
+        Self::with_catalogue(UnitCatalogue::default())
     }
 }
 impl UnitFactory {
-    fn new() -> Self {
+    /// Build units with the stats of the catalogue
+    pub fn with_catalogue(catalogue: UnitCatalogue) -> Self {
         UnitFactory {
             counter: Counter::new(),
+            catalogue,
         }
     }
 
-    pub fn build_unit(&self, unit_type: UnitType) -> Result<Unit, RtsException> {
+    pub fn get_catalogue(&self) -> &UnitCatalogue {
+        &self.catalogue
+    }
+
+    /// Build a unit of the catalogue, with the stats researched by its player
+    pub fn build_unit(
+        &self,
+        unit_type: UnitType,
+        upgrades: &Upgrades,
+    ) -> Result<Unit, RtsException> {
+        // Checked first, an unknown type must not use an identifier
+        self.catalogue.get(&unit_type)?;
         let next_identifier = self.counter.get_next()?;
-        match unit_type {
-            UnitType::Classic => Ok(Unit::from(next_identifier, 20, 10, 5, 15, Vec::new())),
-        }
+        let mut unit = self.catalogue.build(next_identifier, unit_type)?;
+        unit.grow(&upgrades.growth);
+        Ok(unit)
     }
 }
 
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use crate::components::map::Map;
+use crate::components::play_ground::{Coordinate, HasIdentifier, Identifier};
+use crate::components::snapshot::UnitSnapshot;
+use crate::entity::game_event::Turn;
+use crate::entity::player::PlayerIndex;
+use crate::entity::unit::UnitType;
+
+/// Something of a player looking around: a unit or a building
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sight {
+    pub coordinate: Coordinate,
+    /// In tiles
+    pub radius: i16,
+}
+
+impl Sight {
+    /// True if the center of the tile holding the coordinate is in sight,
+    /// close enough and not hidden by the terrain
+    pub fn sees(&self, map: &Map, coordinate: &Coordinate) -> bool {
+        let (x, y) = coordinate.to_tile();
+        let center = Coordinate::from_tiles(x, y);
+        self.coordinate.is_within(&center, self.radius)
+            && map.has_line_of_sight(&self.coordinate, &center)
+    }
+}
+
+/// Tiles of the map a player sees during a turn, the rest is under the fog of war
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisibilityMap {
+    width: u32,
+    height: u32,
+    /// Row by row, from the top left corner
+    tiles: Vec<bool>,
+}
+
+impl VisibilityMap {
+    /// Reveal every tile seen by at least one of the sights
+    pub fn compute(map: &Map, sights: &[Sight]) -> Self {
+        let (width, height) = (*map.get_width(), *map.get_height());
+        let mut tiles = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let center = Coordinate::from_tiles(x, y);
+                tiles.push(sights.iter().any(|sight| sight.sees(map, &center)));
+            }
+        }
+        VisibilityMap {
+            width,
+            height,
+            tiles,
+        }
+    }
+
+    pub fn is_tile_visible(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.tiles[(y * self.width + x) as usize]
+    }
+
+    /// Coordinates out of the map are never visible
+    pub fn is_visible(&self, coordinate: &Coordinate) -> bool {
+        let (x, y) = coordinate.to_tile();
+        x >= 0 && y >= 0 && self.is_tile_visible(x as u32, y as u32)
+    }
+
+    pub fn get_width(&self) -> &u32 {
+        &self.width
+    }
+
+    pub fn get_height(&self) -> &u32 {
+        &self.height
+    }
+}
+
+/// Where an enemy unit was the last time it was seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastKnown {
+    pub identifier: Identifier,
+    pub owner: Option<PlayerIndex>,
+    pub unit_type: UnitType,
+    pub coordinate: Coordinate,
+    pub turn: Turn,
+}
+
+/// What a player sees now and remembers of the enemies
+#[derive(Debug, Clone, Default)]
+pub struct PlayerVision {
+    visibility: Option<VisibilityMap>,
+    memory: BTreeMap<Identifier, LastKnown>,
+}
+
+impl PlayerVision {
+    /// Look at the map again: enemies in sight are remembered where they stand,
+    /// remembered enemies which are not where they were are forgotten
+    pub fn update(&mut self, turn: Turn, visibility: VisibilityMap, enemies: &[UnitSnapshot]) {
+        self.memory
+            .retain(|_, known| !visibility.is_visible(&known.coordinate));
+        for placed in enemies
+            .iter()
+            .filter(|placed| visibility.is_visible(&placed.coordinate))
+        {
+            self.memory.insert(
+                placed.unit.get_identifier(),
+                LastKnown {
+                    identifier: placed.unit.get_identifier(),
+                    owner: *placed.unit.get_owner(),
+                    unit_type: *placed.unit.get_unit_type(),
+                    coordinate: placed.coordinate,
+                    turn,
+                },
+            );
+        }
+        self.visibility = Some(visibility);
+    }
+
+    pub fn get_visibility(&self) -> Option<&VisibilityMap> {
+        self.visibility.as_ref()
+    }
+
+    /// Where the enemy was last seen, if it is still remembered
+    pub fn get_remembered(&self, identifier: &Identifier) -> Option<&LastKnown> {
+        self.memory.get(identifier)
+    }
+
+    /// Enemies seen before and remembered in places out of sight
+    pub fn get_last_known(&self, visibility: &VisibilityMap) -> Vec<LastKnown> {
+        self.memory
+            .values()
+            .filter(|known| !visibility.is_visible(&known.coordinate))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_visibility {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{
+        duel, duel_on, walk, TestClientGameState, ViewpointClientGameState,
+    };
+    use crate::components::map::{Map, Terrain};
+    use crate::components::play_ground::Coordinate;
+    use crate::components::snapshot::UnitSnapshot;
+    use crate::entity::game_actions::Action;
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::unit::{Unit, UnitType};
+
+    use super::{PlayerVision, Sight, VisibilityMap};
+
+    #[test]
+    pub fn should_reveal_tiles_around_sights() {
+        let map = Map::new(10, 5);
+        let sights = [Sight {
+            coordinate: Coordinate::from_tiles(1, 1),
+            radius: 2,
+        }];
+        let visibility = VisibilityMap::compute(&map, &sights);
+
+        assert!(visibility.is_tile_visible(3, 1));
+        assert!(!visibility.is_tile_visible(4, 1));
+        assert!(!visibility.is_tile_visible(3, 3));
+        assert!(visibility.is_visible(&Coordinate::new(3400, 1000)));
+        assert!(!visibility.is_visible(&Coordinate::from_tiles(-1, 1)));
+    }
+
+    #[test]
+    pub fn should_remember_enemies_out_of_sight() {
+        let map = Map::new(10, 5);
+        let mut enemy = Unit::from(7, 20, 10, 5, 2, Vec::new());
+        enemy.set_owner(1);
+        let at = |x| {
+            vec![UnitSnapshot {
+                unit: enemy.clone(),
+                coordinate: Coordinate::from_tiles(x, 1),
+            }]
+        };
+        let looking_at = |x| {
+            VisibilityMap::compute(
+                &map,
+                &[Sight {
+                    coordinate: Coordinate::from_tiles(x, 1),
+                    radius: 1,
+                }],
+            )
+        };
+
+        let mut vision = PlayerVision::default();
+        vision.update(1, looking_at(1), &at(2));
+        assert!(vision.get_last_known(&looking_at(1)).is_empty());
+
+        // Our scout walks away, the enemy is now in the fog
+        vision.update(2, looking_at(8), &at(2));
+        let last_known = vision.get_last_known(&looking_at(8));
+        assert_eq!(1, last_known.len());
+        assert_eq!(Coordinate::from_tiles(2, 1), last_known[0].coordinate);
+        assert_eq!(1, last_known[0].turn);
+
+        // Scouting the old position shows it is empty
+        vision.update(3, looking_at(3), &at(6));
+        assert!(vision.get_last_known(&looking_at(8)).is_empty());
+    }
+
+    #[test]
+    pub fn should_hide_enemies_in_fog_of_war() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = duel(ViewpointClientGameState(0, Rc::clone(&received)));
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.update_visions();
+        assert_eq!(1, game.observe(0).unwrap().enemies.len());
+
+        game.play(1, Action::MoveUnit(2, Coordinate::from_tiles(20, 10)))
+            .unwrap();
+        game.play(1, Action::MoveUnit(2, Coordinate::from_tiles(21, 9)))
+            .unwrap();
+        game.play(0, Action::MoveUnit(1, Coordinate::from_tiles(12, 6)))
+            .unwrap();
+        walk(&game);
+        game.update_observers().unwrap();
+
+        let observation = game.observe(0).unwrap();
+        assert_eq!(1, observation.units.len());
+        assert!(observation.enemies.is_empty());
+        assert_eq!(1, observation.last_known.len());
+        // Where it spawned, next to our unit
+        assert_eq!(
+            Coordinate::from_tiles(1, 0),
+            observation.last_known[0].coordinate
+        );
+        // Hidden units can not be aimed at, nor told from missing ones
+        let hidden = game.play(0, Action::UseSkill(1, 0, 2)).unwrap_err();
+        let missing = game.play(0, Action::UseSkill(1, 0, 7)).unwrap_err();
+        assert_eq!(hidden.to_string().replace('2', "7"), missing.to_string());
+
+        let received = received.borrow();
+        let moves_of = |unit| {
+            received
+                .iter()
+                .filter(|n| {
+                    matches!(n.get_event(), GameEvent::UnitMoved { identifier, .. } if *identifier == unit)
+                })
+                .count()
+        };
+        // Own unit is seen all along its way, at least 5 turns long
+        assert_eq!(
+            Coordinate::from_tiles(12, 6),
+            observation.units[0].coordinate
+        );
+        assert!(moves_of(1) >= 5);
+        // Orders given to the enemy are never told
+        assert!(!received
+            .iter()
+            .any(|n| matches!(n.get_event(), GameEvent::UnitOrdered { identifier: 2, .. })));
+        assert!(!received
+            .iter()
+            .any(|n| matches!(n.get_event(), GameEvent::MoneyChanged { player: 1, .. })));
+    }
+
+    #[test]
+    pub fn should_need_line_of_sight_and_favor_high_ground() {
+        let mut map = Map::new(24, 12);
+        map.set_terrain(2, 0, Terrain::Wall);
+        map.set_terrain(0, 3, Terrain::HighGround);
+        let game = duel_on(map, TestClientGameState());
+
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::MoveUnit(2, Coordinate::from_tiles(4, 0)))
+            .unwrap();
+        walk(&game);
+        assert!(game.play(0, Action::UseSkill(1, 0, 2)).is_err());
+
+        game.play(0, Action::MoveUnit(1, Coordinate::from_tiles(0, 3)))
+            .unwrap();
+        walk(&game);
+        game.play(0, Action::UseSkill(1, 0, 2)).unwrap();
+        let snapshot = game.snapshot();
+        // 10 damage, 2 from the high ground, 5 absorbed by armor
+        assert_eq!(&13, snapshot.units[1].unit.get_health());
+    }
+}
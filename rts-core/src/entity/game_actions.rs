@@ -1,13 +1,51 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::formation::Formation;
 use crate::components::play_ground::{Coordinate, Identifier};
 
+use super::player::GroupName;
 use super::unit::{Unit, UnitType};
 
+/// Units a group command is given to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitSelection {
+    Units(Vec<Identifier>),
+    /// A control group of the player
+    Group(GroupName),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Action {
     // Unit related
     BuyUnit(UnitType),
     MoveUnit(Identifier, Coordinate),
+    /// Walk to the point, fighting enemies met on the way
+    AttackMove(Identifier, Coordinate),
+    /// Walk back and forth between where the unit stands and the point
+    Patrol(Identifier, Coordinate),
+    HoldPosition(Identifier),
+    /// Follower, unit to follow
+    Follow(Identifier, Identifier),
+    /// Forget the order of the unit
+    Stop(Identifier),
+    /// Name of the control group, its units. No units forgets the group
+    AssignGroup(GroupName, Vec<Identifier>),
+    /// Walk together in formation, at the pace of the slowest
+    MoveGroup(UnitSelection, Coordinate, Formation),
+    AttackMoveGroup(UnitSelection, Coordinate, Formation),
+    HoldGroup(UnitSelection),
+    StopGroup(UnitSelection),
+    /// Caster, index of the skill on the caster, target
+    UseSkill(Identifier, usize, Identifier),
+    /// Caster, index of the skill on the caster, center of the area
+    UseSkillAt(Identifier, usize, Coordinate),
     // Player related
     GiveMoneyBatch,
+    /// Name of the technology to research
+    Research(String),
+    Wait,
     // Game related
     EndGame,
 }
@@ -17,8 +55,22 @@ impl Action {
         match &self {
             Action::BuyUnit(t) => format!("Buy new unit {}", t),
             Action::GiveMoneyBatch => String::from("Give new money batch to"),
+            Action::Research(t) => format!("Research {}", t),
+            Action::Wait => String::from("Wait for next turn"),
             Action::EndGame => String::from("Game is over !"),
-            Action::MoveUnit(i, (x, y)) => format!("Move unit {} to ({},{})", i, x, y),
+            Action::MoveUnit(i, c) => format!("Move unit {} to {}", i, c),
+            Action::AttackMove(i, c) => format!("Attack move unit {} to {}", i, c),
+            Action::Patrol(i, c) => format!("Patrol unit {} to {}", i, c),
+            Action::HoldPosition(i) => format!("Unit {} hold position", i),
+            Action::Follow(i, t) => format!("Unit {} follow unit {}", i, t),
+            Action::Stop(i) => format!("Stop unit {}", i),
+            Action::AssignGroup(g, u) => format!("Assign units {:?} to group {}", u, g),
+            Action::MoveGroup(u, c, f) => format!("Move {} to {} in {:?}", u, c, f),
+            Action::AttackMoveGroup(u, c, f) => format!("Attack move {} to {} in {:?}", u, c, f),
+            Action::HoldGroup(u) => format!("{} hold position", u),
+            Action::StopGroup(u) => format!("Stop {}", u),
+            Action::UseSkill(c, s, t) => format!("Unit {} use skill {} on unit {}", c, s, t),
+            Action::UseSkillAt(c, s, p) => format!("Unit {} use skill {} at {}", c, s, p),
         }
     }
 }
@@ -26,7 +78,23 @@ impl Action {
 #[derive(PartialEq, Eq)]
 pub enum MoveState {
     BuyUnit(Unit),
+    /// Not returned anymore, moves are orders like the others
     MoveUnit,
+    /// A unit or a group was given an order, moves included
+    GiveOrder,
+    AssignGroup,
+    UseSkill,
     GiveMoneyBatch,
+    Research,
+    Wait,
     EndGame,
 }
+
+impl Display for UnitSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitSelection::Units(units) => write!(f, "units {:?}", units),
+            UnitSelection::Group(name) => write!(f, "group {}", name),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+
+use crate::components::play_ground::{Coordinate, Identifier};
+use crate::components::scenario::ScenarioOutcome;
+use crate::components::state_hash::StateHash;
+use crate::entity::order::OrderKind;
+use crate::entity::player::PlayerIndex;
+use crate::entity::status_effect::StatusEffect;
+use crate::entity::unit::UnitType;
+
+pub type Turn = u32;
+pub type Sequence = u64;
+
+/// Everything that can happen during a game, as seen by observers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    // Turn related
+    TurnStarted,
+    /// Carries the hash of the game state, to detect desyncs
+    TurnEnded {
+        state_hash: StateHash,
+    },
+    // Player related
+    MoneyChanged {
+        player: PlayerIndex,
+        amount: i32,
+        money: i32,
+    },
+    ResearchStarted {
+        player: PlayerIndex,
+        technology: String,
+        turns: u32,
+    },
+    ResearchCompleted {
+        player: PlayerIndex,
+        technology: String,
+    },
+    ActionRejected {
+        player: PlayerIndex,
+        action: String,
+        reason: String,
+    },
+    // Unit related
+    UnitSpawned {
+        player: PlayerIndex,
+        identifier: Identifier,
+        unit_type: UnitType,
+        coordinate: Coordinate,
+    },
+    /// The player stood alone around the capture point
+    PointCaptured {
+        point: usize,
+        player: PlayerIndex,
+    },
+    UnitOrdered {
+        identifier: Identifier,
+        order: OrderKind,
+        destination: Option<Coordinate>,
+        eta: Option<u32>,
+    },
+    UnitMoved {
+        identifier: Identifier,
+        from: Coordinate,
+        to: Coordinate,
+    },
+    UnitAttacked {
+        attacker: Identifier,
+        target: Identifier,
+        damage: i16,
+        remaining_health: i16,
+    },
+    UnitHealed {
+        healer: Identifier,
+        target: Identifier,
+        amount: i16,
+        health: i16,
+    },
+    UnitDied {
+        identifier: Identifier,
+        killed_by: Identifier,
+    },
+    UnitPromoted {
+        identifier: Identifier,
+        rank: u8,
+        experience: u32,
+    },
+    // Status effects related
+    EffectApplied {
+        identifier: Identifier,
+        effect: StatusEffect,
+    },
+    EffectDamaged {
+        identifier: Identifier,
+        damage: i16,
+        remaining_health: i16,
+    },
+    EffectExpired {
+        identifier: Identifier,
+        effect: StatusEffect,
+    },
+    // Game related
+    /// Scripted text of a scenario
+    ScenarioMessage {
+        text: String,
+    },
+    ScenarioEnded {
+        outcome: ScenarioOutcome,
+    },
+    GameOver,
+}
+
+/// A game event stamped with the turn it happened in and its position
+/// in the stream. Sequence ids start at 0 and never repeat within a game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    sequence: Sequence,
+    turn: Turn,
+    event: GameEvent,
+    /// Players allowed to see the event through the fog of war, None for everybody
+    #[serde(skip)]
+    audience: Option<Vec<PlayerIndex>>,
+}
+
+impl Notification {
+    pub fn new(sequence: Sequence, turn: Turn, event: GameEvent) -> Self {
+        Notification {
+            sequence,
+            turn,
+            event,
+            audience: None,
+        }
+    }
+
+    /// Restrict the event to the given players
+    pub fn with_audience(mut self, audience: Vec<PlayerIndex>) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
+    pub fn is_visible_to(&self, player: PlayerIndex) -> bool {
+        self.audience
+            .as_ref()
+            .map(|audience| audience.contains(&player))
+            .unwrap_or(true)
+    }
+
+    pub fn get_sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    pub fn get_turn(&self) -> &Turn {
+        &self.turn
+    }
+
+    pub fn get_event(&self) -> &GameEvent {
+        &self.event
+    }
+}
+
+/// Stamps events and keeps them until observers are notified
+pub struct EventQueue {
+    turn: Turn,
+    next_sequence: Sequence,
+    pending: Vec<Notification>,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue {
+            turn: 0,
+            next_sequence: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn next_turn(&mut self) -> Turn {
+        self.turn += 1;
+        self.turn
+    }
+
+    pub fn get_turn(&self) -> &Turn {
+        &self.turn
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        let notification = Notification::new(self.next_sequence, self.turn, event);
+        self.enqueue(notification);
+    }
+
+    /// Push an event only the given players are allowed to see
+    pub fn push_for(&mut self, event: GameEvent, audience: Vec<PlayerIndex>) {
+        let notification =
+            Notification::new(self.next_sequence, self.turn, event).with_audience(audience);
+        self.enqueue(notification);
+    }
+
+    fn enqueue(&mut self, notification: Notification) {
+        self.next_sequence += 1;
+        self.pending.push(notification);
+    }
+
+    /// Hand over pending notifications, each one is only given once
+    pub fn drain(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod test_event_queue {
+
+    use crate::components::state_hash::StateHash;
+
+    use super::{EventQueue, GameEvent};
+
+    #[test]
+    pub fn should_stamp_events_in_order() {
+        let mut queue = EventQueue::new();
+        queue.next_turn();
+        queue.push(GameEvent::TurnStarted);
+        queue.push(GameEvent::TurnEnded {
+            state_hash: StateHash::default(),
+        });
+
+        let notifications = queue.drain();
+        assert_eq!(2, notifications.len());
+        assert_eq!(&0, notifications[0].get_sequence());
+        assert_eq!(&1, notifications[1].get_sequence());
+        assert_eq!(&1, notifications[1].get_turn());
+        assert!(queue.drain().is_empty());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod player;
 pub mod skill;
+pub mod status_effect;
 pub mod unit;
+pub mod order;
 pub mod game_actions;
+pub mod game_event;
@@ -0,0 +1,421 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::{distance, Coordinate, Identifier, TILE_SCALE};
+use crate::components::state_hash::{StableHash, StateHasher};
+
+/// Standing order of a unit, carried out by the engine every turn until done or replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Order {
+    /// Walk to the destination, then stop
+    Move(MoveOrder),
+    /// Walk to the destination, fighting the enemies met on the way
+    AttackMove(MoveOrder),
+    /// Walk back and forth between two points forever, fighting the enemies met
+    Patrol {
+        /// Where the current leg started, the next leg goes back there
+        from: Coordinate,
+        path: MoveOrder,
+    },
+    /// Stay in place, fighting the enemies in range
+    Hold,
+    /// Keep close to another unit, the path is found again every turn
+    Follow {
+        target: Identifier,
+        path: Option<MoveOrder>,
+    },
+}
+
+/// Name of an order, for bots and spectators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderKind {
+    Move,
+    AttackMove,
+    Patrol,
+    Hold,
+    Follow,
+    Stop,
+}
+
+impl Order {
+    pub fn get_kind(&self) -> OrderKind {
+        match &self {
+            Order::Move(_) => OrderKind::Move,
+            Order::AttackMove(_) => OrderKind::AttackMove,
+            Order::Patrol { .. } => OrderKind::Patrol,
+            Order::Hold => OrderKind::Hold,
+            Order::Follow { .. } => OrderKind::Follow,
+        }
+    }
+
+    /// True if the unit fights enemies in range instead of walking
+    pub fn engages(&self) -> bool {
+        matches!(
+            self,
+            Order::AttackMove(_) | Order::Patrol { .. } | Order::Hold
+        )
+    }
+
+    /// Path the unit walks this turn, if any
+    pub fn get_path(&self) -> Option<&MoveOrder> {
+        match self {
+            Order::Move(path) | Order::AttackMove(path) | Order::Patrol { path, .. } => Some(path),
+            Order::Follow { path, .. } => path.as_ref(),
+            Order::Hold => None,
+        }
+    }
+
+    pub fn get_path_mut(&mut self) -> Option<&mut MoveOrder> {
+        match self {
+            Order::Move(path) | Order::AttackMove(path) | Order::Patrol { path, .. } => Some(path),
+            Order::Follow { path, .. } => path.as_mut(),
+            Order::Hold => None,
+        }
+    }
+
+    /// Orders to reach a place are over once there, the others never end by themselves
+    pub fn is_done(&self) -> bool {
+        match self {
+            Order::Move(path) | Order::AttackMove(path) => path.is_done(),
+            Order::Patrol { .. } | Order::Hold | Order::Follow { .. } => false,
+        }
+    }
+}
+
+/// Where a unit is walking to, along a path found when the order was given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveOrder {
+    destination: Coordinate,
+    /// Points still to reach in order, the destination is the last one
+    path: VecDeque<Coordinate>,
+    /// Tiles per turn at most, to keep in step with a group
+    pace: Option<i16>,
+}
+
+impl MoveOrder {
+    /// Follow the points of the path, an empty path means staying in place
+    pub fn new(destination: Coordinate, path: Vec<Coordinate>) -> Self {
+        MoveOrder {
+            destination,
+            path: path.into(),
+            pace: None,
+        }
+    }
+
+    pub fn with_pace(mut self, pace: i16) -> Self {
+        self.pace = Some(pace);
+        self
+    }
+
+    pub fn get_pace(&self) -> &Option<i16> {
+        &self.pace
+    }
+
+    /// Speed of a unit walking the path, held back by the pace if any
+    pub fn limit_speed(&self, speed: i16) -> i16 {
+        self.pace.map_or(speed, |pace| pace.min(speed))
+    }
+
+    pub fn get_destination(&self) -> &Coordinate {
+        &self.destination
+    }
+
+    pub fn get_path(&self) -> &VecDeque<Coordinate> {
+        &self.path
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Give up the rest of the path, the unit is as close as it gets
+    pub fn stop(&mut self) {
+        self.path.clear()
+    }
+
+    /// Walk along the path for at most `budget` thousandths of a tile.
+    /// Returns where the unit stops, points reached are left behind.
+    pub fn advance(&mut self, from: Coordinate, budget: i64) -> Coordinate {
+        let mut position = from;
+        let mut budget = budget;
+        while let Some(next) = self.path.front().copied() {
+            let length = distance(&position, &next);
+            if length <= budget {
+                budget -= length;
+                position = next;
+                self.path.pop_front();
+            } else {
+                if budget > 0 {
+                    let dx = (next.x - position.x) as i64 * budget / length;
+                    let dy = (next.y - position.y) as i64 * budget / length;
+                    position = Coordinate::new(position.x + dx as i32, position.y + dy as i32);
+                }
+                break;
+            }
+        }
+        position
+    }
+
+    /// Length of the path left, in thousandths of a tile
+    pub fn get_remaining(&self, from: &Coordinate) -> i64 {
+        let mut position = *from;
+        let mut total = 0;
+        for next in self.path.iter() {
+            total += distance(&position, next);
+            position = *next;
+        }
+        total
+    }
+
+    /// Number of turns before reaching the destination, never for a unit which can not move
+    pub fn get_eta(&self, from: &Coordinate, speed: i16) -> Option<u32> {
+        let per_turn = self.limit_speed(speed) as i64 * TILE_SCALE as i64;
+        let remaining = self.get_remaining(from);
+        if remaining == 0 {
+            Some(0)
+        } else if per_turn <= 0 {
+            None
+        } else {
+            Some(((remaining + per_turn - 1) / per_turn) as u32)
+        }
+    }
+}
+
+impl StableHash for Order {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_i16(match self.get_kind() {
+            OrderKind::Move => 0,
+            OrderKind::AttackMove => 1,
+            OrderKind::Patrol => 2,
+            OrderKind::Hold => 3,
+            OrderKind::Follow => 4,
+            OrderKind::Stop => 5,
+        });
+        match self {
+            Order::Move(path) | Order::AttackMove(path) => hasher.write(path),
+            Order::Patrol { from, path } => {
+                hasher.write(from);
+                hasher.write(path);
+            }
+            Order::Hold => {}
+            Order::Follow { target, path } => {
+                hasher.write_i128(*target);
+                if let Some(path) = path {
+                    hasher.write(path);
+                }
+            }
+        }
+    }
+}
+
+impl StableHash for MoveOrder {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write(&self.destination);
+        hasher.write_u64(self.path.len() as u64);
+        self.path.iter().for_each(|point| hasher.write(point));
+        hasher.write_i16(self.pace.unwrap_or(-1));
+    }
+}
+
+#[cfg(test)]
+mod test_order {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{
+        duel, rich_player, update_unit, walk, RecordingClientGameState, TestClientGameState,
+    };
+    use crate::components::game::Game;
+    use crate::components::game_config::GameConfig;
+    use crate::components::map::{Map, Terrain};
+    use crate::components::play_ground::Coordinate;
+    use crate::entity::game_actions::Action;
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::status_effect::{EffectKind, StatusEffect};
+    use crate::entity::unit::UnitType;
+
+    use super::{MoveOrder, OrderKind};
+
+    #[test]
+    pub fn should_walk_along_path() {
+        let destination = Coordinate::from_tiles(3, 4);
+        let mut order =
+            MoveOrder::new(destination, vec![Coordinate::from_tiles(3, 0), destination]);
+        let start = Coordinate::default();
+        assert_eq!(Some(3), order.get_eta(&start, 3));
+        assert_eq!(None, order.get_eta(&start, 0));
+
+        let position = order.advance(start, 4000);
+        assert_eq!(Coordinate::from_tiles(3, 1), position);
+        assert_eq!(1, order.get_path().len());
+        let position = order.advance(position, 4000);
+        assert_eq!(destination, position);
+        assert!(order.is_done());
+        assert_eq!(Some(0), order.get_eta(&position, 3));
+    }
+
+    #[test]
+    pub fn should_carry_out_standing_orders() {
+        let game = duel(TestClientGameState());
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::MoveUnit(1, Coordinate::from_tiles(20, 0)))
+            .unwrap();
+        walk(&game);
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::BuyUnit(UnitType::Archer)).unwrap();
+
+        // The archer sees farther than its enemy
+        game.play(0, Action::AttackMove(3, Coordinate::from_tiles(23, 0)))
+            .unwrap();
+        game.play(0, Action::Follow(2, 3)).unwrap();
+        assert!(game.play(0, Action::Follow(2, 2)).is_err());
+        assert!(game.play(1, Action::HoldPosition(2)).is_err());
+        for _ in 0..6 {
+            game.begin_turn().unwrap();
+            game.execute_orders().unwrap();
+        }
+        let observation = game.observe(0).unwrap();
+        let follower = observation.units[0].coordinate;
+        let attacker = observation.units[1].coordinate;
+        // It stopped to fight once the enemy was in sight
+        assert_eq!(Coordinate::from_tiles(10, 0), attacker);
+        assert!(follower.is_within(&attacker, 2));
+        assert_eq!(OrderKind::Follow, observation.orders[0].order);
+        assert_eq!(OrderKind::AttackMove, observation.orders[1].order);
+        assert!(game.observe(1).unwrap().units.is_empty());
+
+        // Once the enemy is dead it walks on
+        walk(&game);
+        let observation = game.observe(0).unwrap();
+        assert_eq!(
+            Coordinate::from_tiles(23, 0),
+            observation.units[1].coordinate
+        );
+        assert_eq!(1, observation.orders.len());
+
+        game.play(0, Action::HoldPosition(2)).unwrap();
+        let orders = game.observe(0).unwrap().orders;
+        assert_eq!(
+            (OrderKind::Hold, None),
+            (orders[0].order, orders[0].destination)
+        );
+        game.play(0, Action::Stop(2)).unwrap();
+        assert!(game.observe(0).unwrap().orders.is_empty());
+    }
+
+    #[test]
+    pub fn should_only_follow_units_in_sight() {
+        let game = duel(TestClientGameState());
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::MoveUnit(1, Coordinate::from_tiles(20, 0)))
+            .unwrap();
+        walk(&game);
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+
+        // Hidden units can not be told from missing ones
+        let hidden = game.play(0, Action::Follow(2, 1)).unwrap_err();
+        let missing = game.play(0, Action::Follow(2, 7)).unwrap_err();
+        assert_eq!(hidden.to_string().replace('1', "7"), missing.to_string());
+
+        game.play(1, Action::MoveUnit(1, Coordinate::from_tiles(6, 0)))
+            .unwrap();
+        walk(&game);
+        game.play(0, Action::Follow(2, 1)).unwrap();
+        game.update_visions();
+        let seen = game.snapshot().units[0].coordinate;
+
+        // Out of sight, the follower heads to where its target was last seen
+        game.play(1, Action::MoveUnit(1, Coordinate::from_tiles(20, 0)))
+            .unwrap();
+        for _ in 0..20 {
+            game.move_units(&[]).unwrap();
+        }
+        game.refresh_paths();
+        let orders = game.observe(0).unwrap().orders;
+        assert_eq!(Some(seen), orders[0].destination);
+    }
+
+    #[test]
+    pub fn should_patrol_back_and_forth() {
+        let game = duel(TestClientGameState());
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(0, Action::Patrol(1, Coordinate::from_tiles(6, 0)))
+            .unwrap();
+        let mut positions = Vec::new();
+        for _ in 0..5 {
+            game.execute_orders().unwrap();
+            positions.push(game.observe(0).unwrap().units[0].coordinate.to_tile().0);
+        }
+        assert_eq!(vec![3, 6, 3, 0, 3], positions);
+        assert_eq!(OrderKind::Patrol, game.observe(0).unwrap().orders[0].order);
+    }
+
+    #[test]
+    pub fn should_walk_over_several_turns() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut map = Map::new(24, 12);
+        map.set_terrain(5, 5, Terrain::Water);
+        let config = GameConfig {
+            map,
+            ..GameConfig::default()
+        };
+        let game = Game::with_config(
+            vec![rich_player("Tigran")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+            config,
+        );
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        assert!(game
+            .play(0, Action::MoveUnit(1, Coordinate::from_tiles(5, 5)))
+            .is_err());
+
+        game.play(0, Action::MoveUnit(1, Coordinate::from_tiles(8, 0)))
+            .unwrap();
+        let orders = game.observe(0).unwrap().orders;
+        assert_eq!(Some(3), orders[0].eta);
+
+        game.execute_orders().unwrap();
+        let observation = game.observe(0).unwrap();
+        assert_eq!(
+            Coordinate::from_tiles(3, 0),
+            observation.units[0].coordinate
+        );
+        assert_eq!(Some(2), observation.orders[0].eta);
+
+        walk(&game);
+        let observation = game.observe(0).unwrap();
+        assert_eq!(
+            Coordinate::from_tiles(8, 0),
+            observation.units[0].coordinate
+        );
+        assert!(observation.orders.is_empty());
+        game.update_observers().unwrap();
+        let moves = received
+            .borrow()
+            .iter()
+            .filter(|n| matches!(n.get_event(), GameEvent::UnitMoved { .. }))
+            .count();
+        assert_eq!(3, moves);
+
+        // Slowed units are told the time they will really take
+        update_unit(&game, 1, |unit| {
+            unit.apply_effect(StatusEffect::new(EffectKind::Slow { percent: 50 }, 10))
+        });
+        game.play(0, Action::MoveUnit(1, Coordinate::from_tiles(0, 0)))
+            .unwrap();
+        let expected = game.observe(0).unwrap().orders[0].eta;
+        assert_eq!(Some(8), expected);
+        game.update_observers().unwrap();
+        let eta = received
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|n| match n.get_event() {
+                GameEvent::UnitOrdered { eta, .. } => Some(*eta),
+                _ => None,
+            });
+        assert_eq!(Some(expected), eta);
+    }
+}
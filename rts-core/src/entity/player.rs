@@ -1,7 +1,21 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
-use crate::exceptions::RtsException;
+use crate::components::match_result::{ScoreBreakdown, ScoreSource};
+use crate::components::play_ground::Identifier;
+use crate::components::state_hash::{StableHash, StateHasher};
+use crate::components::tech_tree::ResearchState;
 use crate::entity::game_actions::Action;
+use crate::exceptions::RtsException;
+
+/// Position of a player in the game, used as owner of units
+pub type PlayerIndex = usize;
+
+/// Players of the same team are allies, they win or lose together
+pub type TeamId = usize;
+
+/// Name given by a player to a set of its units
+pub type GroupName = String;
 
 pub trait TurnStrategyRequester {
     fn request(&self) -> Result<Action, RtsException>;
@@ -13,6 +27,11 @@ where
 {
     name: String,
     wallet: Wallet,
+    score: i32,
+    breakdown: ScoreBreakdown,
+    research: ResearchState,
+    /// Control groups, kept from one turn to the next
+    groups: BTreeMap<GroupName, Vec<Identifier>>,
     turn_strategy_requester: TurnStrategy,
 }
 
@@ -24,6 +43,10 @@ where
         Player {
             name,
             wallet: Wallet::new(),
+            score: 0,
+            breakdown: ScoreBreakdown::default(),
+            research: ResearchState::default(),
+            groups: BTreeMap::new(),
             turn_strategy_requester,
         }
     }
@@ -40,6 +63,60 @@ where
         &self.wallet.money
     }
 
+    pub fn get_score(&self) -> &i32 {
+        &self.score
+    }
+
+    pub fn update_score(&mut self, amount: i32) {
+        self.score = self.score.saturating_add(amount)
+    }
+
+    /// Add to the score, remembering what it was earned for
+    pub fn score_points(&mut self, source: ScoreSource, amount: i32) {
+        self.update_score(amount);
+        self.breakdown.add(source, amount);
+    }
+
+    pub fn get_breakdown(&self) -> &ScoreBreakdown {
+        &self.breakdown
+    }
+
+    /// Technologies researched by the player, and the one in progress
+    pub fn get_research(&self) -> &ResearchState {
+        &self.research
+    }
+
+    pub fn get_research_mut(&mut self) -> &mut ResearchState {
+        &mut self.research
+    }
+
+    pub fn get_groups(&self) -> &BTreeMap<GroupName, Vec<Identifier>> {
+        &self.groups
+    }
+
+    pub fn get_group(&self, name: &str) -> Option<&Vec<Identifier>> {
+        self.groups.get(name)
+    }
+
+    /// Replace the units of a control group, an empty group is forgotten
+    pub fn set_group(&mut self, name: GroupName, mut units: Vec<Identifier>) {
+        units.sort_unstable();
+        units.dedup();
+        if units.is_empty() {
+            self.groups.remove(&name);
+        } else {
+            self.groups.insert(name, units);
+        }
+    }
+
+    /// Take a dead unit out of every control group
+    pub fn forget_unit(&mut self, identifier: Identifier) {
+        self.groups
+            .values_mut()
+            .for_each(|units| units.retain(|unit| *unit != identifier));
+        self.groups.retain(|_, units| !units.is_empty());
+    }
+
     pub fn update_money(&mut self, amount: i32) -> Option<i32> {
         if let Some(res) = self.wallet.money.checked_add(amount) {
             self.wallet.money = res;
@@ -50,6 +127,26 @@ where
     }
 }
 
+impl<TurnStrategy> StableHash for Player<TurnStrategy>
+where
+    TurnStrategy: TurnStrategyRequester,
+{
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_str(&self.name);
+        hasher.write_i32(self.wallet.money);
+        hasher.write_i32(self.score);
+        hasher.write_i32(self.breakdown.kills);
+        hasher.write_i32(self.breakdown.creeps);
+        hasher.write_i32(self.breakdown.objectives);
+        hasher.write(&self.research);
+        for (name, units) in &self.groups {
+            hasher.write_str(name);
+            hasher.write_u64(units.len() as u64);
+            units.iter().for_each(|unit| hasher.write_i128(*unit));
+        }
+    }
+}
+
 struct Wallet {
     money: i32,
 }
@@ -1,8 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::{Coordinate, TILE_SCALE};
+use crate::components::state_hash::{StableHash, StateHasher};
+use crate::entity::status_effect::StatusEffect;
+
+/// Area hit by a skill, sizes are in tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "shape")]
+pub enum SkillShape {
+    /// Only the targeted unit
+    Single,
+    /// Every unit around the targeted point
+    Circle { radius: i16 },
+    /// Every unit on a straight line from the caster toward the point
+    Line { width: i16 },
+    /// Every unit in front of the caster toward the point,
+    /// the cone is `width` tiles wide at the end of its range
+    Cone { width: i16 },
+}
+
+/// What a skill does to the units it hits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkillKind {
+    /// Hurts enemies, armor reduces the damage
+    Damage,
+    /// Gives health back to allies, up to their maximum
+    Heal,
+}
+
+/// Nature of the damage of a skill, armor classes resist it more or less
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DamageType {
+    Physical,
+    Piercing,
+    Magic,
+}
+
+/// How a skill gets better each time its unit ranks up
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkillUpgrade {
+    /// Added to the damage, or to the health given back by heals
+    #[serde(default)]
+    pub damage: i16,
+    /// Removed from the cooldown, in ms
+    #[serde(default)]
+    pub cooldown_reduction: i32,
+    #[serde(default)]
+    pub mana_cost_reduction: i8,
+}
+
+impl SkillShape {
+    /// True if the position is hit by a skill of the caster aimed at the point.
+    /// Only integers are used, so the result is the same on every machine.
+    pub fn covers(
+        &self,
+        caster: &Coordinate,
+        point: &Coordinate,
+        range: i16,
+        position: &Coordinate,
+    ) -> bool {
+        let scale = TILE_SCALE as i128;
+        let axis = ((point.x - caster.x) as i128, (point.y - caster.y) as i128);
+        let target = (
+            (position.x - caster.x) as i128,
+            (position.y - caster.y) as i128,
+        );
+        let axis_squared = axis.0 * axis.0 + axis.1 * axis.1;
+        // Projection on the axis and distance to the axis, both times |axis|
+        let along = axis.0 * target.0 + axis.1 * target.1;
+        let across = (axis.0 * target.1 - axis.1 * target.0).abs();
+        let reach = range as i128 * scale;
+        let in_front = along > 0 && along * along <= reach * reach * axis_squared;
+        match &self {
+            SkillShape::Single => position == point,
+            SkillShape::Circle { radius } => point.is_within(position, *radius),
+            SkillShape::Line { width } => {
+                let full = *width as i128 * scale;
+                in_front && 4 * across * across <= full * full * axis_squared
+            }
+            SkillShape::Cone { width } => {
+                // Half width grows from 0 at the caster to width / 2 at the end
+                in_front && 2 * across * reach <= *width as i128 * scale * along
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Skill {
     mana_cost: i8,
     damage: i16,
     cooldown: i32, // ms
+    shape: SkillShape,
+    kind: SkillKind,
+    damage_type: DamageType,
+    /// Put on the target when the skill hits
+    effect: Option<StatusEffect>,
 
     // mutable fields
     actual_cooldown: i32, // ms
@@ -16,19 +109,78 @@ impl Skill {
             mana_cost,
             damage,
             cooldown,
+            shape: SkillShape::Single,
+            kind: SkillKind::Damage,
+            damage_type: DamageType::Physical,
+            effect: None,
             actual_cooldown: cooldown,
             level: 0,
         }
     }
 
+    /// Make the skill hit every unit in the area
+    pub fn with_shape(mut self, shape: SkillShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Make the skill heal allies instead of hurting enemies,
+    /// its damage is then the health given back
+    pub fn with_kind(mut self, kind: SkillKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Change the nature of the damage, physical by default
+    pub fn with_damage_type(mut self, damage_type: DamageType) -> Self {
+        self.damage_type = damage_type;
+        self
+    }
+
+    /// Make the skill put an effect on its target
+    pub fn with_effect(mut self, effect: StatusEffect) -> Self {
+        self.effect = Some(effect);
+        self
+    }
+
     pub fn update_level(&mut self) {
         self.level += 1
     }
 
+    /// Go to the next level, cooldown and mana cost never get negative
+    pub fn level_up(&mut self, upgrade: &SkillUpgrade) {
+        self.damage = self.damage.saturating_add(upgrade.damage);
+        self.cooldown = self
+            .cooldown
+            .saturating_sub(upgrade.cooldown_reduction)
+            .max(0);
+        self.actual_cooldown = self.actual_cooldown.min(self.cooldown);
+        self.mana_cost = self
+            .mana_cost
+            .saturating_sub(upgrade.mana_cost_reduction)
+            .max(0);
+        self.update_level()
+    }
+
     pub fn update_actual_cooldown(&mut self, new_cooldown: i32) {
         self.actual_cooldown = new_cooldown
     }
 
+    /// Skill can be cast once his cooldown is over
+    pub fn is_ready(&self) -> bool {
+        self.actual_cooldown <= 0
+    }
+
+    /// Cast the skill, it has to cool down again before next use
+    pub fn trigger(&mut self) {
+        self.actual_cooldown = self.cooldown
+    }
+
+    /// Make time pass for the skill cooldown
+    pub fn cool_down(&mut self, elapsed: i32) {
+        self.actual_cooldown = (self.actual_cooldown - elapsed).max(0)
+    }
+
     pub fn get_actual_cooldown(&self) -> &i32 {
         &self.actual_cooldown
     }
@@ -44,4 +196,207 @@ impl Skill {
     pub fn get_cooldown(&self) -> &i32 {
         &self.cooldown
     }
+
+    pub fn get_level(&self) -> &i8 {
+        &self.level
+    }
+
+    pub fn get_shape(&self) -> &SkillShape {
+        &self.shape
+    }
+
+    pub fn get_kind(&self) -> &SkillKind {
+        &self.kind
+    }
+
+    pub fn get_damage_type(&self) -> &DamageType {
+        &self.damage_type
+    }
+
+    pub fn get_effect(&self) -> &Option<StatusEffect> {
+        &self.effect
+    }
+}
+
+impl StableHash for Skill {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_i16(self.mana_cost as i16);
+        hasher.write_i16(self.damage);
+        hasher.write_i32(self.cooldown);
+        hasher.write_i32(self.actual_cooldown);
+        hasher.write_i16(self.level as i16);
+        let (shape, size) = match self.shape {
+            SkillShape::Single => (0, 0),
+            SkillShape::Circle { radius } => (1, radius),
+            SkillShape::Line { width } => (2, width),
+            SkillShape::Cone { width } => (3, width),
+        };
+        hasher.write_i16(shape);
+        hasher.write_i16(size);
+        hasher.write_i16(match self.kind {
+            SkillKind::Damage => 0,
+            SkillKind::Heal => 1,
+        });
+        hasher.write_i16(match self.damage_type {
+            DamageType::Physical => 0,
+            DamageType::Piercing => 1,
+            DamageType::Magic => 2,
+        });
+        if let Some(effect) = &self.effect {
+            hasher.write(effect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_skill {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{
+        place_unit, rich_player, walk, RecordingClientGameState,
+    };
+    use crate::components::game::Game;
+    use crate::components::play_ground::Coordinate;
+    use crate::entity::game_actions::Action;
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::unit::{Unit, UnitType};
+
+    use super::{Skill, SkillKind, SkillShape, SkillUpgrade};
+
+    #[test]
+    pub fn should_level_up() {
+        let mut skill = Skill::from(5, 10, 2000);
+        let upgrade = SkillUpgrade {
+            damage: 3,
+            cooldown_reduction: 1500,
+            mana_cost_reduction: 2,
+        };
+        skill.level_up(&upgrade);
+        assert_eq!(
+            (&13, &500, &3, &1),
+            (
+                skill.get_damage(),
+                skill.get_cooldown(),
+                skill.get_mana_cost(),
+                skill.get_level()
+            )
+        );
+        assert_eq!(&500, skill.get_actual_cooldown());
+
+        skill.level_up(&upgrade);
+        skill.level_up(&upgrade);
+        assert_eq!(
+            (&0, &0, &3),
+            (
+                skill.get_cooldown(),
+                skill.get_mana_cost(),
+                skill.get_level()
+            )
+        );
+
+        // Upgrades read from a catalogue may be far off
+        let mut skill = Skill::from(100, 10, 2000);
+        skill.level_up(&SkillUpgrade {
+            cooldown_reduction: i32::MIN,
+            mana_cost_reduction: -100,
+            ..SkillUpgrade::default()
+        });
+        assert_eq!(
+            (&i32::MAX, &i8::MAX),
+            (skill.get_cooldown(), skill.get_mana_cost())
+        );
+        skill.level_up(&SkillUpgrade {
+            mana_cost_reduction: i8::MAX,
+            ..SkillUpgrade::default()
+        });
+        assert_eq!(&0, skill.get_mana_cost());
+    }
+
+    #[test]
+    pub fn should_cover_shapes() {
+        let caster = Coordinate::from_tiles(0, 0);
+        let point = Coordinate::from_tiles(4, 0);
+        let covers = |shape: SkillShape, x, y| {
+            shape.covers(&caster, &point, 6, &Coordinate::from_tiles(x, y))
+        };
+
+        assert!(covers(SkillShape::Single, 4, 0));
+        assert!(!covers(SkillShape::Single, 4, 1));
+
+        let circle = SkillShape::Circle { radius: 1 };
+        assert!(covers(circle, 5, 0) && covers(circle, 4, 1));
+        assert!(!covers(circle, 5, 1));
+
+        let line = SkillShape::Line { width: 2 };
+        assert!(covers(line, 6, 1) && covers(line, 1, -1));
+        assert!(!covers(line, 7, 0) && !covers(line, -1, 0) && !covers(line, 3, 2));
+
+        let cone = SkillShape::Cone { width: 6 };
+        assert!(covers(cone, 6, 3) && covers(cone, 2, 1));
+        assert!(!covers(cone, 2, 2) && !covers(cone, 7, 0));
+    }
+
+    #[test]
+    pub fn should_hit_area_and_heal_allies() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::new(
+            vec![rich_player("Tigran"), rich_player("Emma")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+        );
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::MoveUnit(2, Coordinate::from_tiles(3, 0)))
+            .unwrap();
+        game.play(1, Action::MoveUnit(3, Coordinate::from_tiles(3, 1)))
+            .unwrap();
+        walk(&game);
+
+        let mut healer = Unit::from(
+            100,
+            20,
+            10,
+            0,
+            5,
+            vec![
+                Skill::from(0, 10, 0).with_shape(SkillShape::Circle { radius: 1 }),
+                Skill::from(0, 5, 0)
+                    .with_shape(SkillShape::Circle { radius: 2 })
+                    .with_kind(SkillKind::Heal),
+                Skill::from(0, 5, 0).with_kind(SkillKind::Heal),
+                Skill::from(0, 5, 0),
+            ],
+        );
+        healer.set_owner(0);
+        place_unit(&game, healer);
+
+        game.play(0, Action::UseSkillAt(100, 0, Coordinate::from_tiles(3, 0)))
+            .unwrap();
+        let snapshot = game.snapshot();
+        assert_eq!(&20, snapshot.units[0].unit.get_health());
+        assert_eq!(&15, snapshot.units[1].unit.get_health());
+        assert_eq!(&15, snapshot.units[2].unit.get_health());
+
+        game.play(1, Action::UseSkill(2, 0, 1)).unwrap();
+        game.play(0, Action::UseSkillAt(100, 1, Coordinate::default()))
+            .unwrap();
+        assert_eq!(&20, game.snapshot().units[0].unit.get_health());
+        game.update_observers().unwrap();
+        assert!(received.borrow().iter().any(|n| matches!(
+            n.get_event(),
+            GameEvent::UnitHealed {
+                target: 1,
+                amount: 5,
+                ..
+            }
+        )));
+
+        // Single targets follow the same rules as areas
+        assert!(game.play(0, Action::UseSkill(100, 2, 2)).is_err());
+        assert!(game.play(0, Action::UseSkill(100, 3, 1)).is_err());
+        game.play(0, Action::UseSkill(100, 2, 1)).unwrap();
+        game.play(0, Action::UseSkill(100, 3, 2)).unwrap();
+    }
 }
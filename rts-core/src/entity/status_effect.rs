@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::play_ground::Identifier;
+use crate::components::state_hash::{StableHash, StateHasher};
+use crate::entity::player::PlayerIndex;
+
+/// Most poisons a unit can suffer at once, the oldest one is replaced
+pub const MAX_POISON_STACKS: usize = 3;
+
+/// What an effect does to the unit carrying it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum EffectKind {
+    /// Can not move nor use skills
+    Stun,
+    /// Skills cool down and the unit walks slower, by the given percent
+    Slow { percent: i16 },
+    /// Damage taken at the start of each turn, armor does not help
+    Poison { damage: i16 },
+    /// Armor added, or removed when negative
+    Armor { amount: i16 },
+    /// Damage absorbed before health is lost
+    Shield { amount: i16 },
+}
+
+/// How a new effect is combined with the ones already on the unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackRule {
+    /// Only one effect is kept: the strongest, lasting the longest
+    Refresh,
+    /// Effects add up, each one with its own duration, up to a limit
+    Stack(usize),
+    /// Amounts add up in a single effect, lasting the longest
+    Merge,
+}
+
+/// A timed effect put on a unit by a skill
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    /// Number of turns the effect is still active for
+    pub turns: u32,
+    /// Unit which applied the effect, credited for kills
+    pub source: Option<Identifier>,
+    pub source_owner: Option<PlayerIndex>,
+}
+
+impl EffectKind {
+    pub fn get_stack_rule(&self) -> StackRule {
+        match &self {
+            EffectKind::Stun | EffectKind::Slow { .. } => StackRule::Refresh,
+            EffectKind::Poison { .. } => StackRule::Stack(MAX_POISON_STACKS),
+            EffectKind::Armor { .. } | EffectKind::Shield { .. } => StackRule::Merge,
+        }
+    }
+
+    /// Effects of the same family are combined together.
+    /// Armor buffs and debuffs are different families.
+    pub fn is_same_family(&self, other: &EffectKind) -> bool {
+        match (self, other) {
+            (EffectKind::Armor { amount: a }, EffectKind::Armor { amount: b }) => {
+                a.signum() == b.signum()
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    /// Strength of the effect, to keep the strongest one
+    pub fn get_magnitude(&self) -> i16 {
+        match &self {
+            EffectKind::Stun => 0,
+            EffectKind::Slow { percent } => *percent,
+            EffectKind::Poison { damage } => *damage,
+            EffectKind::Armor { amount } | EffectKind::Shield { amount } => amount.abs(),
+        }
+    }
+
+    fn merge(&self, other: &EffectKind) -> EffectKind {
+        match (self, other) {
+            (EffectKind::Armor { amount: a }, EffectKind::Armor { amount: b }) => {
+                EffectKind::Armor {
+                    amount: a.saturating_add(*b),
+                }
+            }
+            (EffectKind::Shield { amount: a }, EffectKind::Shield { amount: b }) => {
+                EffectKind::Shield {
+                    amount: a.saturating_add(*b),
+                }
+            }
+            _ => *self,
+        }
+    }
+}
+
+impl StatusEffect {
+    pub fn new(kind: EffectKind, turns: u32) -> Self {
+        StatusEffect {
+            kind,
+            turns,
+            source: None,
+            source_owner: None,
+        }
+    }
+
+    /// Remember who applied the effect
+    pub fn from_source(mut self, source: Identifier, owner: Option<PlayerIndex>) -> Self {
+        self.source = Some(source);
+        self.source_owner = owner;
+        self
+    }
+
+    /// Add the effect to the ones of a unit, following its stack rule
+    pub fn stack_on(self, effects: &mut Vec<StatusEffect>) {
+        let mut same_family = effects
+            .iter()
+            .enumerate()
+            .filter(|(_, effect)| effect.kind.is_same_family(&self.kind))
+            .map(|(i, _)| i);
+        match self.kind.get_stack_rule() {
+            StackRule::Refresh => match same_family.next() {
+                Some(i) => {
+                    let current = &mut effects[i];
+                    if self.kind.get_magnitude() >= current.kind.get_magnitude() {
+                        current.kind = self.kind;
+                        current.source = self.source;
+                        current.source_owner = self.source_owner;
+                    }
+                    current.turns = current.turns.max(self.turns);
+                }
+                None => effects.push(self),
+            },
+            StackRule::Stack(max) => {
+                let stacks: Vec<usize> = same_family.collect();
+                if stacks.len() >= max {
+                    effects.remove(stacks[0]);
+                }
+                effects.push(self);
+            }
+            StackRule::Merge => match same_family.next() {
+                Some(i) => {
+                    let current = &mut effects[i];
+                    current.kind = current.kind.merge(&self.kind);
+                    current.turns = current.turns.max(self.turns);
+                }
+                None => effects.push(self),
+            },
+        }
+    }
+}
+
+impl StableHash for StatusEffect {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        let (tag, value) = match self.kind {
+            EffectKind::Stun => (0, 0),
+            EffectKind::Slow { percent } => (1, percent),
+            EffectKind::Poison { damage } => (2, damage),
+            EffectKind::Armor { amount } => (3, amount),
+            EffectKind::Shield { amount } => (4, amount),
+        };
+        hasher.write_i16(tag);
+        hasher.write_i16(value);
+        hasher.write_u64(self.turns as u64);
+        hasher.write_i128(self.source.unwrap_or(-1));
+        hasher.write_i64(self.source_owner.map(|owner| owner as i64).unwrap_or(-1));
+    }
+}
+
+#[cfg(test)]
+mod test_status_effect {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::components::game::test_game::{update_unit, RecordingClientGameState};
+    use crate::components::game::Game;
+    use crate::components::play_ground::Coordinate;
+    use crate::components::state_hash::StateHasher;
+    use crate::components::turn_strategy::TurnStrategy;
+    use crate::entity::game_actions::Action;
+    use crate::entity::game_event::GameEvent;
+    use crate::entity::player::Player;
+    use crate::entity::unit::UnitType;
+
+    use super::{EffectKind, StatusEffect, MAX_POISON_STACKS};
+
+    #[test]
+    pub fn should_stack_by_rule() {
+        let mut effects = Vec::new();
+        StatusEffect::new(EffectKind::Slow { percent: 30 }, 2).stack_on(&mut effects);
+        StatusEffect::new(EffectKind::Slow { percent: 10 }, 5).stack_on(&mut effects);
+        assert_eq!(
+            vec![StatusEffect::new(EffectKind::Slow { percent: 30 }, 5)],
+            effects
+        );
+
+        for _ in 0..MAX_POISON_STACKS + 1 {
+            StatusEffect::new(EffectKind::Poison { damage: 1 }, 3).stack_on(&mut effects);
+        }
+        assert_eq!(1 + MAX_POISON_STACKS, effects.len());
+
+        StatusEffect::new(EffectKind::Shield { amount: 4 }, 1).stack_on(&mut effects);
+        StatusEffect::new(EffectKind::Shield { amount: 6 }, 2).stack_on(&mut effects);
+        assert_eq!(
+            Some(&StatusEffect::new(EffectKind::Shield { amount: 10 }, 2)),
+            effects.last()
+        );
+
+        // A debuff does not cancel a buff
+        StatusEffect::new(EffectKind::Armor { amount: 3 }, 2).stack_on(&mut effects);
+        StatusEffect::new(EffectKind::Armor { amount: -2 }, 2).stack_on(&mut effects);
+        assert_eq!(2 + 1 + MAX_POISON_STACKS + 1, effects.len());
+    }
+
+    #[test]
+    pub fn should_hash_who_gets_the_kill() {
+        let hash_of = |effect: &StatusEffect| {
+            let mut hasher = StateHasher::new();
+            hasher.write(effect);
+            hasher.finish()
+        };
+        let poison = StatusEffect::new(EffectKind::Poison { damage: 10 }, 2);
+        assert_ne!(
+            hash_of(&poison.from_source(1, Some(0))),
+            hash_of(&poison.from_source(1, None))
+        );
+    }
+
+    #[test]
+    pub fn should_stun_and_poison_units() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let waiting = |name: &str| {
+            let mut player = Player::new(name.to_string(), TurnStrategy::scripted(Vec::new()));
+            player.update_money(100);
+            player
+        };
+        let game = Game::new(
+            vec![waiting("Tigran"), waiting("Emma")],
+            vec![RecordingClientGameState(Rc::clone(&received))],
+        );
+        game.play(0, Action::BuyUnit(UnitType::Classic)).unwrap();
+        game.play(1, Action::BuyUnit(UnitType::Classic)).unwrap();
+
+        update_unit(&game, 2, |unit| {
+            unit.apply_effect(StatusEffect::new(EffectKind::Stun, 1));
+            unit.apply_effect(
+                StatusEffect::new(EffectKind::Poison { damage: 10 }, 2).from_source(1, Some(0)),
+            );
+        });
+        game.play_turn().unwrap();
+        assert!(game.play(1, Action::UseSkill(2, 0, 1)).is_err());
+        assert!(game
+            .play(1, Action::MoveUnit(2, Coordinate::from_tiles(1, 1)))
+            .is_err());
+
+        game.play_turn().unwrap();
+        game.update_observers().unwrap();
+        assert!(received.borrow().iter().any(|n| matches!(
+            n.get_event(),
+            GameEvent::UnitDied {
+                identifier: 2,
+                killed_by: 1
+            }
+        )));
+        assert_eq!(&20, game.get_players()[0].borrow().get_score());
+    }
+}
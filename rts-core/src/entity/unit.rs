@@ -1,9 +1,18 @@
+use serde::{Deserialize, Serialize};
+
 use crate::components::play_ground::{HasIdentifier, Identifier};
-use crate::entity::skill::Skill;
+use crate::components::state_hash::{StableHash, StateHasher};
+use crate::entity::order::Order;
+use crate::entity::player::PlayerIndex;
+use crate::entity::skill::{Skill, SkillKind, SkillUpgrade};
+use crate::entity::status_effect::{EffectKind, StatusEffect};
 use crate::exceptions::RtsException;
 use std::fmt::Display;
 
-const CLASSIC_UNIT_COST: i16 = 20;
+/// Tiles walked per turn by units not given a speed
+const DEFAULT_SPEED: i16 = 3;
+/// Footprint of units not given one, in thousandths of a tile
+const DEFAULT_RADIUS: i32 = 300;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Unit {
@@ -12,6 +21,13 @@ pub struct Unit {
     max_mana: i16,
     max_armor: i16,
     max_range: i16,
+    unit_type: UnitType,
+    armor_class: ArmorClass,
+    /// Tiles walked per turn
+    speed: i16,
+    /// Footprint, other units can not stand closer, in thousandths of a tile
+    radius: i32,
+    owner: Option<PlayerIndex>,
 
     // mutable fields
     health: i16,
@@ -19,11 +35,50 @@ pub struct Unit {
     armor: i16,
     range: i16,
     skills: Vec<Skill>,
+    effects: Vec<StatusEffect>,
+    experience: u32,
+    rank: u8,
+    order: Option<Order>,
+}
+
+/// Stats gained when a unit ranks up, added to the maximum and current values
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatGrowth {
+    #[serde(default)]
+    pub health: i16,
+    #[serde(default)]
+    pub mana: i16,
+    #[serde(default)]
+    pub armor: i16,
+    #[serde(default)]
+    pub range: i16,
 }
 
-#[derive(Debug)]
+/// What happened to the effects of a unit when a turn started
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EffectTick {
+    /// Damage taken from poisons, after shields
+    pub damage: i16,
+    /// Poison which dealt the last damage, credited if the unit dies
+    pub poison: Option<StatusEffect>,
+    pub expired: Vec<StatusEffect>,
+}
+
+/// Stats of each type are given by the unit catalogue of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum UnitType {
     Classic,
+    Archer,
+    Knight,
+    Mage,
+}
+
+/// How a unit is protected, each class resists damage types differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ArmorClass {
+    Light,
+    Heavy,
+    Fortified,
 }
 
 impl HasIdentifier for Unit {
@@ -36,14 +91,6 @@ impl HasIdentifier for Unit {
     }
 }
 
-impl UnitType {
-    pub fn get_cost(&self) -> i16 {
-        match &self {
-            UnitType::Classic => CLASSIC_UNIT_COST,
-        }
-    }
-}
-
 impl Display for Unit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -70,14 +117,45 @@ impl Unit {
             max_mana,
             max_armor,
             max_range,
+            unit_type: UnitType::Classic,
+            armor_class: ArmorClass::Light,
+            speed: DEFAULT_SPEED,
+            radius: DEFAULT_RADIUS,
+            owner: None,
             health: max_health,
             mana: max_mana,
             armor: max_armor,
             range: max_range,
             skills,
+            effects: Vec::new(),
+            experience: 0,
+            rank: 0,
+            order: None,
         }
     }
 
+    /// Classic by default
+    pub fn with_unit_type(mut self, unit_type: UnitType) -> Self {
+        self.unit_type = unit_type;
+        self
+    }
+
+    /// Light by default
+    pub fn with_armor_class(mut self, armor_class: ArmorClass) -> Self {
+        self.armor_class = armor_class;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: i16) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: i32) -> Self {
+        self.radius = radius;
+        self
+    }
+
     pub fn update_health(&mut self, new_health: i16) -> Result<(), RtsException> {
         if let Some(res) = Unit::update_attribut_under_max(self.health, self.max_health, new_health)
         {
@@ -142,6 +220,10 @@ impl Unit {
         }
     }
 
+    pub fn get_max_health(&self) -> &i16 {
+        &self.max_health
+    }
+
     pub fn get_health(&self) -> &i16 {
         &self.health
     }
@@ -157,12 +239,250 @@ impl Unit {
     pub fn get_range(&self) -> &i16 {
         &self.range
     }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn get_unit_type(&self) -> &UnitType {
+        &self.unit_type
+    }
+
+    pub fn get_armor_class(&self) -> &ArmorClass {
+        &self.armor_class
+    }
+
+    pub fn get_speed(&self) -> &i16 {
+        &self.speed
+    }
+
+    pub fn get_radius(&self) -> &i32 {
+        &self.radius
+    }
+
+    /// Tiles the unit can walk this turn, less when slowed and none when stunned
+    pub fn get_effective_speed(&self) -> i16 {
+        if self.is_stunned() {
+            0
+        } else {
+            (self.speed.max(0) as i32 * (100 - self.get_slow() as i32) / 100) as i16
+        }
+    }
+
+    /// Standing order of the unit, none when idle
+    pub fn get_order(&self) -> &Option<Order> {
+        &self.order
+    }
+
+    pub fn get_order_mut(&mut self) -> Option<&mut Order> {
+        self.order.as_mut()
+    }
+
+    pub fn set_order(&mut self, order: Option<Order>) {
+        self.order = order
+    }
+
+    pub fn get_owner(&self) -> &Option<PlayerIndex> {
+        &self.owner
+    }
+
+    pub fn set_owner(&mut self, owner: PlayerIndex) {
+        self.owner = Some(owner)
+    }
+
+    pub fn get_skills(&self) -> &[Skill] {
+        &self.skills
+    }
+
+    /// Damage of the strongest attack of the unit
+    pub fn get_threat(&self) -> i16 {
+        self.skills
+            .iter()
+            .filter(|skill| skill.get_kind() == &SkillKind::Damage)
+            .map(|skill| *skill.get_damage())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn get_skill_mut(&mut self, index: usize) -> Option<&mut Skill> {
+        self.skills.get_mut(index)
+    }
+
+    /// Make time pass for all skills of the unit, slower when slowed
+    pub fn cool_down_skills(&mut self, elapsed: i32) {
+        let slowed = elapsed * (100 - self.get_slow() as i32) / 100;
+        self.skills
+            .iter_mut()
+            .for_each(|skill| skill.cool_down(slowed))
+    }
+
+    pub fn get_experience(&self) -> &u32 {
+        &self.experience
+    }
+
+    /// Number of times the unit ranked up, 0 for a recruit
+    pub fn get_rank(&self) -> &u8 {
+        &self.rank
+    }
+
+    pub fn gain_experience(&mut self, amount: u32) {
+        self.experience = self.experience.saturating_add(amount)
+    }
+
+    /// Rank up: stats grow and every skill goes to its next level
+    pub fn promote(&mut self, growth: &StatGrowth, upgrade: &SkillUpgrade) {
+        self.grow(growth);
+        self.skills
+            .iter_mut()
+            .for_each(|skill| skill.level_up(upgrade));
+        self.rank = self.rank.saturating_add(1);
+    }
+
+    /// Raise the maximum stats, current ones gain the same amount
+    pub fn grow(&mut self, growth: &StatGrowth) {
+        self.max_health = self.max_health.saturating_add(growth.health);
+        self.max_mana = self.max_mana.saturating_add(growth.mana);
+        self.max_armor = self.max_armor.saturating_add(growth.armor);
+        self.max_range = self.max_range.saturating_add(growth.range);
+        self.health = self
+            .health
+            .saturating_add(growth.health)
+            .min(self.max_health);
+        self.mana = self.mana.saturating_add(growth.mana).min(self.max_mana);
+        self.armor = self.armor.saturating_add(growth.armor).min(self.max_armor);
+        self.range = self.range.saturating_add(growth.range).min(self.max_range);
+    }
+
+    pub fn get_effects(&self) -> &[StatusEffect] {
+        &self.effects
+    }
+
+    /// Add the effect to the unit, following the stack rule of its kind
+    pub fn apply_effect(&mut self, effect: StatusEffect) {
+        effect.stack_on(&mut self.effects)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.kind == EffectKind::Stun)
+    }
+
+    /// Strongest slow on the unit, in percent
+    pub fn get_slow(&self) -> i16 {
+        self.effects
+            .iter()
+            .filter_map(|effect| match effect.kind {
+                EffectKind::Slow { percent } => Some(percent.clamp(0, 100)),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Armor with buffs and debuffs, never negative
+    pub fn get_effective_armor(&self) -> i16 {
+        let bonus: i16 = self
+            .effects
+            .iter()
+            .filter_map(|effect| match effect.kind {
+                EffectKind::Armor { amount } => Some(amount),
+                _ => None,
+            })
+            .fold(0, |total, amount| total.saturating_add(amount));
+        self.armor.saturating_add(bonus).max(0)
+    }
+
+    /// Lose health, shields absorb the damage first.
+    /// Returns the health actually lost.
+    pub fn take_damage(&mut self, damage: i16) -> Result<i16, RtsException> {
+        let mut left = damage.max(0);
+        for effect in self.effects.iter_mut() {
+            if let EffectKind::Shield { amount } = &mut effect.kind {
+                let absorbed = left.min(*amount);
+                *amount -= absorbed;
+                left -= absorbed;
+            }
+        }
+        self.effects
+            .retain(|effect| effect.kind != EffectKind::Shield { amount: 0 });
+        self.update_health(-left)?;
+        Ok(left)
+    }
+
+    /// Start a turn: expired effects are removed, poisons deal their damage
+    /// and every other effect gets one turn shorter
+    pub fn tick_effects(&mut self) -> Result<EffectTick, RtsException> {
+        let mut tick = EffectTick::default();
+        let (expired, active): (Vec<StatusEffect>, Vec<StatusEffect>) =
+            self.effects.drain(..).partition(|effect| effect.turns == 0);
+        tick.expired = expired;
+        self.effects = active;
+
+        let mut poison_damage: i16 = 0;
+        for effect in self.effects.iter_mut() {
+            if let EffectKind::Poison { damage } = effect.kind {
+                poison_damage = poison_damage.saturating_add(damage);
+                tick.poison = Some(*effect);
+            }
+            effect.turns -= 1;
+        }
+        if poison_damage > 0 {
+            tick.damage = self.take_damage(poison_damage)?;
+        }
+        Ok(tick)
+    }
+}
+
+impl StableHash for Unit {
+    fn stable_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_i128(self.identifier);
+        hasher.write_i16(match self.unit_type {
+            UnitType::Classic => 0,
+            UnitType::Archer => 1,
+            UnitType::Knight => 2,
+            UnitType::Mage => 3,
+        });
+        hasher.write_i16(match self.armor_class {
+            ArmorClass::Light => 0,
+            ArmorClass::Heavy => 1,
+            ArmorClass::Fortified => 2,
+        });
+        hasher.write_i64(self.owner.map(|owner| owner as i64).unwrap_or(-1));
+        for value in [
+            self.max_health,
+            self.max_mana,
+            self.max_armor,
+            self.max_range,
+            self.health,
+            self.mana,
+            self.armor,
+            self.range,
+        ] {
+            hasher.write_i16(value);
+        }
+        hasher.write_u64(self.skills.len() as u64);
+        self.skills.iter().for_each(|skill| hasher.write(skill));
+        hasher.write_u64(self.effects.len() as u64);
+        self.effects.iter().for_each(|effect| hasher.write(effect));
+        hasher.write_u64(self.experience as u64);
+        hasher.write_i16(self.rank as i16);
+        hasher.write_i16(self.speed);
+        hasher.write_i32(self.radius);
+        match &self.order {
+            Some(order) => hasher.write(order),
+            None => hasher.write_u64(0),
+        }
+    }
 }
 
 impl Display for UnitType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             UnitType::Classic => write!(f, "Classic"),
+            UnitType::Archer => write!(f, "Archer"),
+            UnitType::Knight => write!(f, "Knight"),
+            UnitType::Mage => write!(f, "Mage"),
         }
     }
 }
@@ -170,6 +490,8 @@ impl Display for UnitType {
 #[cfg(test)]
 mod test_unit {
 
+    use crate::entity::status_effect::{EffectKind, StatusEffect};
+
     use super::Unit;
 
     // Damage cases
@@ -265,4 +587,27 @@ mod test_unit {
             assert_eq!(&9, unit.get_range());
         }
     }
+
+    // Status effects
+    #[test]
+    pub fn should_absorb_damage_with_shield_then_expire() {
+        let mut unit = Unit::from(1, 10, 0, 2, 0, Vec::new());
+        unit.apply_effect(StatusEffect::new(EffectKind::Shield { amount: 3 }, 1));
+        unit.apply_effect(StatusEffect::new(EffectKind::Poison { damage: 2 }, 2));
+        unit.apply_effect(StatusEffect::new(EffectKind::Armor { amount: -5 }, 1));
+        assert_eq!(0, unit.get_effective_armor());
+
+        assert_eq!(2, unit.take_damage(5).unwrap());
+        assert_eq!(&8, unit.get_health());
+
+        let tick = unit.tick_effects().unwrap();
+        assert_eq!(2, tick.damage);
+        assert!(tick.expired.is_empty());
+        assert_eq!(1, unit.tick_effects().unwrap().expired.len());
+        let tick = unit.tick_effects().unwrap();
+        assert_eq!(0, tick.damage);
+        assert_eq!(1, tick.expired.len());
+        assert_eq!(&4, unit.get_health());
+        assert!(unit.get_effects().is_empty());
+    }
 }
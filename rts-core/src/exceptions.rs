@@ -34,3 +34,5 @@ impl Display for RtsException {
         }
     }
 }
+
+impl std::error::Error for RtsException {}
@@ -1,4 +1,6 @@
 DROP TABLE IF EXISTS matchs;
+DROP TABLE IF EXISTS game_players;
+DROP TABLE IF EXISTS games;
 DROP TABLE IF EXISTS ais;
 DROP TABLE IF EXISTS users;
 
@@ -15,12 +17,26 @@ CREATE TABLE ais (
   code VARCHAR NOT NULL
 );
 
+CREATE TABLE games (
+  id SERIAL PRIMARY KEY
+);
+
+CREATE TABLE game_players (
+  game INT NOT NULL REFERENCES games (id),
+  player INT NOT NULL REFERENCES users (id),
+  position INT NOT NULL,
+  PRIMARY KEY (game, position),
+  UNIQUE (game, player)
+);
+
 CREATE TABLE MATCHS(
   id SERIAL PRIMARY KEY,
   player INT REFERENCES users (id),
-  game INT NOT NULL,
+  game INT NOT NULL REFERENCES games (id),
   score INT NOT NULL,
-  winner BOOLEAN NOT NULL
+  winner BOOLEAN NOT NULL,
+  team INT NOT NULL DEFAULT 0,
+  UNIQUE (game, player)
 );
 
 
@@ -1,30 +1,291 @@
-use crate::repositories::leader_repo::MatchRepository;
+use crate::repositories::game_repo::GameRepository;
+use crate::repositories::leader_repo::{already_submitted, MatchRepository};
+use crate::repositories::user_repo::UserRepository;
 use crate::AppState;
-use actix_web::{get, post, HttpResponse};
+use actix_web::{get, post, HttpRequest, HttpResponse};
 use actix_web::{web, Responder};
+use log::{error, warn};
+use rts_core::components::game_config::GameConfig;
+use rts_core::components::match_result::MatchResult;
+use rts_core::components::replay::{Replay, ReplayPlayer};
+use sqlx::PgPool;
 
-use crate::dto::input::NewMatchDto;
-use crate::dto::output::LeaderBoardDto;
+use crate::dto::input::{NewGameDto, NewMatchDto};
+use crate::dto::output::{GameDto, LeaderBoardDto};
+use crate::exceptions::WebServerException;
 use crate::models::game::*;
+use crate::models::user::User;
+
+use super::user_controller::get_current_user;
+
+/// Longest replay the server accepts to play again
+const MAX_REPLAY_TURNS: usize = 10_000;
+/// Money of every player when a ranked game starts
+const STARTING_MONEY: i32 = 0;
 
 #[post("/leaderboard")]
 pub async fn insert_new_match(
+    req: HttpRequest,
     state: web::Data<AppState<'_>>,
     new_match_dto: web::Json<NewMatchDto>,
 ) -> impl Responder {
+    // Authenticate the user
+    let user = match get_current_user(&req, &state).await {
+        Ok(None) => return HttpResponse::Unauthorized().body("You are not logged in."),
+        Err(e) => {
+            error!("Failed to authenticate a match submission: {}", e);
+            return HttpResponse::InternalServerError().body(format!("{}", e));
+        }
+        Ok(Some(user)) => user,
+    };
+
     let dto = new_match_dto.into_inner();
-    match MatchRepository::insert(&state.pg_pool, prepare_dto_for_insert(dto)).await {
+    let result = match verify_match(&state.pg_pool, &user, &dto).await {
+        Ok(result) => result,
+        Err(e) => {
+            warn!(
+                "Rejected match of game {} for player {}: {}",
+                dto.game, user.id, e
+            );
+            return match e {
+                WebServerException::Sql(_) => {
+                    HttpResponse::InternalServerError().body(format!("{}", e))
+                }
+                _ => HttpResponse::BadRequest().body(format!("{}", e)),
+            };
+        }
+    };
+
+    let team = result
+        .get_team(dto.player_index)
+        .copied()
+        .unwrap_or_default() as i32;
+    match MatchRepository::insert(&state.pg_pool, prepare_dto_for_insert(dto, team)).await {
         Ok(_) => HttpResponse::Ok().body("Successfuly insert match"),
-        Err(e) => HttpResponse::InternalServerError().body(format!("{}", e)),
+        Err(e @ WebServerException::Sql(_)) => {
+            error!("{}", e);
+            HttpResponse::InternalServerError().body(format!("{}", e))
+        }
+        Err(e) => HttpResponse::BadRequest().body(format!("{}", e)),
+    }
+}
+
+/// Rules of a ranked game, the game identifier seeds its random numbers
+fn ranked_config(game: i32) -> GameConfig {
+    GameConfig {
+        seed: game as u64,
+        ..GameConfig::default()
+    }
+}
+
+/// Record the players of a ranked game before it is played,
+/// the server only takes results of the games it started
+#[post("/games")]
+pub async fn start_ranked_game(
+    req: HttpRequest,
+    state: web::Data<AppState<'_>>,
+    new_game_dto: web::Json<NewGameDto>,
+) -> impl Responder {
+    let user = match get_current_user(&req, &state).await {
+        Ok(None) => return HttpResponse::Unauthorized().body("You are not logged in."),
+        Err(e) => {
+            error!("Failed to authenticate a game start: {}", e);
+            return HttpResponse::InternalServerError().body(format!("{}", e));
+        }
+        Ok(Some(user)) => user,
+    };
+
+    match register_game(&state.pg_pool, &user, &new_game_dto.players).await {
+        Ok(game) => HttpResponse::Ok().json(GameDto { game }),
+        Err(e) => {
+            warn!("Rejected game of {:?}: {}", new_game_dto.players, e);
+            match e {
+                WebServerException::Sql(_) => {
+                    HttpResponse::InternalServerError().body(format!("{}", e))
+                }
+                _ => HttpResponse::BadRequest().body(format!("{}", e)),
+            }
+        }
+    }
+}
+
+async fn register_game(
+    pool: &PgPool,
+    user: &User,
+    usernames: &[String],
+) -> Result<i32, WebServerException> {
+    check_players(user, usernames)?;
+    let mut players = Vec::with_capacity(usernames.len());
+    for username in usernames {
+        let player = UserRepository::find_by_username(pool, username)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                WebServerException::InvalidMatch(format!("Unknown player {}", username))
+            })?;
+        players.push(player.id);
+    }
+    GameRepository::insert(pool, &players).await
+}
+
+/// A ranked game has at least two different players, the user among them
+fn check_players(user: &User, usernames: &[String]) -> Result<(), WebServerException> {
+    if usernames.len() < 2 {
+        return Err(WebServerException::InvalidMatch(String::from(
+            "A ranked game needs at least two players",
+        )));
+    }
+    if !usernames.contains(&user.username) {
+        return Err(WebServerException::InvalidMatch(format!(
+            "{} can only start the games it plays",
+            user.username
+        )));
+    }
+    for (i, username) in usernames.iter().enumerate() {
+        if usernames[..i].contains(username) {
+            return Err(WebServerException::InvalidMatch(format!(
+                "{} plays twice",
+                username
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check the submission against the game recorded when it started, then play
+/// the actions of the replay again with the rules of the server and check they
+/// give the claimed result to the player. In team games the player wins along
+/// the whole team.
+async fn verify_match(
+    pool: &PgPool,
+    user: &User,
+    dto: &NewMatchDto,
+) -> Result<MatchResult, WebServerException> {
+    let participants = GameRepository::find_participants(pool, dto.game).await?;
+    let submitted = MatchRepository::exists(pool, dto.game, user.id).await?;
+    check_submission(user, dto, &participants, submitted)?;
+
+    let ranked = ranked_replay(dto);
+    // Playing a game is CPU bound, keep it away from the workers
+    let result = web::block(move || play_again(&ranked))
+        .await
+        .map_err(|e| WebServerException::InvalidMatch(format!("Failed to play replay: {}", e)))??;
+    check_result(dto, &result)?;
+    Ok(result)
+}
+
+/// The user submits its own result, once, for a game the server started
+/// with the players of the replay in the same order
+fn check_submission(
+    user: &User,
+    dto: &NewMatchDto,
+    participants: &[ParticipantDo],
+    submitted: bool,
+) -> Result<(), WebServerException> {
+    if dto.player != user.id {
+        return Err(WebServerException::InvalidMatch(format!(
+            "{} can not submit the match of player {}",
+            user.username, dto.player
+        )));
+    }
+    if participants.is_empty() {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Game {} was not started by the server",
+            dto.game
+        )));
     }
+    let replay = &dto.replay;
+    let same_players = replay.players.len() == participants.len()
+        && replay
+            .players
+            .iter()
+            .zip(participants)
+            .all(|(player, participant)| player.name == participant.username);
+    if !same_players {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Players of the replay are not the ones of game {}",
+            dto.game
+        )));
+    }
+    let is_player = participants
+        .get(dto.player_index)
+        .map(|participant| participant.player == user.id)
+        .unwrap_or(false);
+    if !is_player {
+        return Err(WebServerException::InvalidMatch(format!(
+            "{} is not player {} of game {}",
+            user.username, dto.player_index, dto.game
+        )));
+    }
+    if submitted {
+        return Err(already_submitted(dto.game));
+    }
+    if replay.turns.len() > MAX_REPLAY_TURNS {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Replay is longer than {} turns",
+            MAX_REPLAY_TURNS
+        )));
+    }
+    if replay.config.seed != ranked_config(dto.game).seed {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Replay is not the one of game {}",
+            dto.game
+        )));
+    }
+    Ok(())
+}
+
+/// Only the actions and hashes come from the client, the rules and the players' money are ours
+fn ranked_replay(dto: &NewMatchDto) -> Replay {
+    let mut ranked = Replay::new(
+        ranked_config(dto.game),
+        dto.replay
+            .players
+            .iter()
+            .map(|player| ReplayPlayer {
+                name: player.name.clone(),
+                money: STARTING_MONEY,
+            })
+            .collect(),
+    );
+    ranked.turns = dto.replay.turns.clone();
+    ranked.hashes = dto.replay.hashes.clone();
+    ranked
+}
+
+/// Outcome of the replay, fails when it does not give the recorded hashes
+fn play_again(ranked: &Replay) -> Result<MatchResult, WebServerException> {
+    ranked
+        .simulate()
+        .map_err(|e| WebServerException::InvalidMatch(e.to_string()))
+}
+
+fn check_result(dto: &NewMatchDto, result: &MatchResult) -> Result<(), WebServerException> {
+    if result.get_score(dto.player_index) != Some(&dto.score) {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Claimed score {} but the replay gives {:?}",
+            dto.score,
+            result.get_score(dto.player_index)
+        )));
+    }
+    if result.is_winner(dto.player_index) != dto.winner {
+        return Err(WebServerException::InvalidMatch(format!(
+            "Claimed winner {} but the replay gives {}",
+            dto.winner,
+            result.is_winner(dto.player_index)
+        )));
+    }
+    Ok(())
 }
 
-fn prepare_dto_for_insert(dto: NewMatchDto) -> NewMatchDo {
+fn prepare_dto_for_insert(dto: NewMatchDto, team: i32) -> NewMatchDo {
     NewMatchDo {
         game: dto.game,
         player: dto.player,
         score: dto.score,
         winner: dto.winner,
+        team,
     }
 }
 
@@ -36,12 +297,10 @@ pub async fn leaderboard(state: web::Data<AppState<'_>>, max: web::Path<i32>) ->
         .await
         .map(transform_to_dto);
 
-    dbg!(&leader_board);
-
     match leader_board {
         Ok(board) => HttpResponse::Ok().json(board),
         Err(e) => {
-            println!("{}", e);
+            error!("{}", e);
             HttpResponse::InternalServerError().body("Failed to retrieve leader board")
         }
     }
@@ -58,3 +317,122 @@ fn transform_to_dto(leader_board: Vec<LeaderBoardRowDo>) -> Vec<LeaderBoardDto>
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test_leader_board_controller {
+
+    use rts_core::components::replay::{Replay, ReplayPlayer};
+    use rts_core::components::state_hash::StateHash;
+    use rts_core::entity::game_actions::Action;
+
+    use crate::dto::input::NewMatchDto;
+    use crate::exceptions::WebServerException;
+    use crate::models::game::ParticipantDo;
+    use crate::models::user::User;
+
+    use super::{check_result, check_submission, play_again, ranked_config, ranked_replay};
+
+    const GAME: i32 = 7;
+
+    fn tigran() -> User {
+        User {
+            id: 1,
+            username: "Tigran".to_string(),
+            password: String::new(),
+            email: "tigran@rts.dev".to_string(),
+        }
+    }
+
+    fn participants() -> Vec<ParticipantDo> {
+        vec![
+            ParticipantDo {
+                player: 1,
+                username: "Tigran".to_string(),
+            },
+            ParticipantDo {
+                player: 2,
+                username: "Emma".to_string(),
+            },
+        ]
+    }
+
+    /// Tigran's result of a game ended at once, between the given players
+    fn submission(players: &[&str]) -> NewMatchDto {
+        let mut replay = Replay::new(
+            ranked_config(GAME),
+            players
+                .iter()
+                .map(|name| ReplayPlayer {
+                    name: name.to_string(),
+                    money: 0,
+                })
+                .collect(),
+        );
+        replay.turns = vec![vec![Action::EndGame, Action::Wait]];
+        NewMatchDto {
+            game: GAME,
+            player: 1,
+            score: 0,
+            winner: false,
+            player_index: 0,
+            replay,
+        }
+    }
+
+    fn is_invalid(result: Result<(), WebServerException>) -> bool {
+        matches!(result, Err(WebServerException::InvalidMatch(_)))
+    }
+
+    #[test]
+    pub fn should_accept_result_of_started_game() {
+        let dto = submission(&["Tigran", "Emma"]);
+        assert!(check_submission(&tigran(), &dto, &participants(), false).is_ok());
+        let result = play_again(&ranked_replay(&dto)).unwrap();
+        assert!(check_result(&dto, &result).is_ok());
+    }
+
+    #[test]
+    pub fn should_reject_forged_opponent() {
+        let user = tigran();
+        let forged = submission(&["Tigran", "Mallory"]);
+        assert!(is_invalid(check_submission(
+            &user,
+            &forged,
+            &participants(),
+            false
+        )));
+        let swapped = submission(&["Emma", "Tigran"]);
+        assert!(is_invalid(check_submission(
+            &user,
+            &swapped,
+            &participants(),
+            false
+        )));
+        let unknown_game = submission(&["Tigran", "Emma"]);
+        assert!(is_invalid(check_submission(
+            &user,
+            &unknown_game,
+            &[],
+            false
+        )));
+    }
+
+    #[test]
+    pub fn should_reject_resubmission() {
+        let dto = submission(&["Tigran", "Emma"]);
+        match check_submission(&tigran(), &dto, &participants(), true) {
+            Err(e) => assert!(e.to_string().contains("already submitted")),
+            Ok(_) => panic!("A second submission was accepted"),
+        }
+    }
+
+    #[test]
+    pub fn should_reject_replay_with_mismatched_hashes() {
+        let mut dto = submission(&["Tigran", "Emma"]);
+        dto.replay.hashes = vec![StateHash(0)];
+        assert!(matches!(
+            play_again(&ranked_replay(&dto)),
+            Err(WebServerException::InvalidMatch(_))
+        ));
+    }
+}
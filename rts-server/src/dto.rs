@@ -12,6 +12,12 @@ pub mod output {
         pub looses: i64,
     }
 
+    /// Ranked game recorded by the server, its results can be submitted
+    #[derive(Debug, Serialize)]
+    pub struct GameDto {
+        pub game: i32,
+    }
+
     // ===  User ===
 
     #[derive(Serialize)]
@@ -47,6 +53,7 @@ pub mod output {
 
 pub mod input {
 
+    use rts_core::components::replay::Replay;
     use serde::Deserialize;
 
     // === User ===
@@ -73,12 +80,23 @@ pub mod input {
         Gist { username: String, hash: String },
     }
 
+    // === Leader Board ===
+
+    #[derive(Deserialize)]
+    pub struct NewGameDto {
+        /// Usernames of the players, in their order in the game
+        pub players: Vec<String>,
+    }
+
     #[derive(Deserialize)]
     pub struct NewMatchDto {
         pub game: i32,
         pub player: i32,
         pub score: i32,
         pub winner: bool,
+        /// Position of the player in the replay
+        pub player_index: usize,
+        /// Proof of the result, played again by the server
+        pub replay: Replay,
     }
-
 }
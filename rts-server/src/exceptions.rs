@@ -5,12 +5,14 @@ pub enum WebServerException {
     Sql(String),
     HashPassword,
     User(String),
+    InvalidMatch(String),
 }
 
 impl Display for WebServerException {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             WebServerException::Sql(m) | WebServerException::User(m) => write!(f, "{}", m),
+            WebServerException::InvalidMatch(m) => write!(f, "Invalid match: {}", m),
             WebServerException::HashPassword => write!(f, "Failed to hash password"),
         }
     }
@@ -5,7 +5,9 @@ pub mod models;
 pub mod repositories;
 
 use crate::controllers::ai_controller::submit_ai;
-use crate::controllers::leader_board_controller::leaderboard;
+use crate::controllers::leader_board_controller::{
+    insert_new_match, leaderboard, start_ranked_game,
+};
 use crate::controllers::user_controller::{login, login_status, logout, register};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -63,7 +65,11 @@ async fn main() -> std::io::Result<()> {
             // ai submit route
             .service(submit_ai)
             // leaderboard route
-            .service(leaderboard);
+            .service(leaderboard)
+            // ranked game start route
+            .service(start_ranked_game)
+            // verified match results route
+            .service(insert_new_match);
 
         // TODO not great, we should only use this for routes defined in the front, and send a 404 for the rest
         let index_fallback = fs::NamedFile::open("./rts-server/static/index.html")
@@ -76,6 +82,8 @@ async fn main() -> std::io::Result<()> {
         App::new()
             // bind the database
             .app_data(app_state)
+            // match results come with their replay, bigger than the default limit
+            .app_data(web::JsonConfig::default().limit(4 * 1024 * 1024))
             // enable logger
             .wrap(middleware::Logger::default())
             // add the api routes
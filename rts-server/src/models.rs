@@ -49,11 +49,20 @@ pub mod game {
         pub looses: i64,
     }
 
+    /// Player registered in a ranked game when the server started it
+    #[derive(Debug, Clone, sqlx::FromRow)]
+    pub struct ParticipantDo {
+        pub player: i32,
+        pub username: String,
+    }
+
     #[derive(Debug)]
     pub struct NewMatchDo {
         pub game: i32,
         pub player: i32,
         pub score: i32,
         pub winner: bool,
+        /// Team of the player in the game, alone players have their own team
+        pub team: i32,
     }
 }
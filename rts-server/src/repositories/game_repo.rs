@@ -0,0 +1,62 @@
+use sqlx::PgPool;
+
+use crate::exceptions::WebServerException;
+use crate::models::game::ParticipantDo;
+
+use super::user_repo;
+
+pub struct GameRepository;
+
+const TABLE_NAME: &str = "games";
+const PLAYERS_TABLE_NAME: &str = "game_players";
+
+impl GameRepository {
+    /// Record a ranked game and its players, in their order in the game.
+    /// Returns the identifier of the game.
+    pub async fn insert(pool: &PgPool, players: &[i32]) -> Result<i32, WebServerException> {
+        let failed = |_: sqlx::Error| {
+            WebServerException::Sql(format!("Failed to insert game of players {:?}", players))
+        };
+        let mut transaction = pool.begin().await.map_err(failed)?;
+        let game = sqlx::query_scalar::<_, i32>(&format!(
+            "INSERT INTO {} DEFAULT VALUES RETURNING id",
+            TABLE_NAME
+        ))
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(failed)?;
+        for (position, player) in players.iter().enumerate() {
+            sqlx::query(&format!(
+                "INSERT INTO {} (game, player, position) VALUES ($1, $2, $3)",
+                PLAYERS_TABLE_NAME
+            ))
+            .bind(game)
+            .bind(player)
+            .bind(position as i32)
+            .execute(&mut transaction)
+            .await
+            .map_err(failed)?;
+        }
+        transaction.commit().await.map_err(failed)?;
+        Ok(game)
+    }
+
+    /// Players of the game in their order, none if the server never started it
+    pub async fn find_participants(
+        pool: &PgPool,
+        game: i32,
+    ) -> Result<Vec<ParticipantDo>, WebServerException> {
+        sqlx::query_as::<_, ParticipantDo>(&format!(
+            "SELECT {players}.player, {users}.username FROM {players}
+            JOIN {users} ON {users}.id = {players}.player
+            WHERE {players}.game = $1
+            ORDER BY {players}.position",
+            players = PLAYERS_TABLE_NAME,
+            users = user_repo::TABLE_NAME
+        ))
+        .bind(game)
+        .fetch_all(pool)
+        .await
+        .map_err(|_| WebServerException::Sql(format!("Failed to fetch players of game {}", game)))
+    }
+}
@@ -8,20 +8,56 @@ use super::user_repo;
 pub struct MatchRepository;
 
 const TABLE_NAME: &str = "matchs";
+/// Postgres error code when a row breaks a UNIQUE constraint
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Error given when a player submits the result of a game twice
+pub fn already_submitted(game: i32) -> WebServerException {
+    WebServerException::InvalidMatch(format!("Result of game {} was already submitted", game))
+}
 
 impl MatchRepository {
     pub async fn insert(pool: &PgPool, new_match: NewMatchDo) -> Result<(), WebServerException> {
         sqlx::query(&format!(
-            "INSERT INTO {} (player, game, score) VALUES ($1, $2, $3)",
+            "INSERT INTO {} (player, game, score, winner, team) VALUES ($1, $2, $3, $4, $5)",
             TABLE_NAME
         ))
         .bind(new_match.player)
         .bind(new_match.game)
         .bind(new_match.score)
+        .bind(new_match.winner)
+        .bind(new_match.team)
         .execute(pool)
         .await
         .map(|_| ())
-        .map_err(|_| WebServerException::Sql(format!("Failed to insert match {:?}", new_match)))
+        .map_err(|e| match e {
+            // Another submission of the player won the race
+            sqlx::Error::Database(ref error)
+                if error.code().as_deref() == Some(UNIQUE_VIOLATION) =>
+            {
+                already_submitted(new_match.game)
+            }
+            _ => WebServerException::Sql(format!("Failed to insert match {:?}", new_match)),
+        })
+    }
+
+    /// True if the player already submitted the result of the game
+    pub async fn exists(pool: &PgPool, game: i32, player: i32) -> Result<bool, WebServerException> {
+        sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM {} WHERE game = $1 AND player = $2",
+            TABLE_NAME
+        ))
+        .bind(game)
+        .bind(player)
+        .fetch_one(pool)
+        .await
+        .map(|count| count > 0)
+        .map_err(|_| {
+            WebServerException::Sql(format!(
+                "Failed to look for match {} of player {}",
+                game, player
+            ))
+        })
     }
 
     pub async fn fetch_leader_board(
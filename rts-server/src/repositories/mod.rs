@@ -1,3 +1,4 @@
 pub mod user_repo;
 pub mod ai_repo;
 pub mod leader_repo;
+pub mod game_repo;
@@ -0,0 +1,133 @@
+pub mod terminal;
+pub mod tui_displayer;
+pub mod viewer;
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::stdout;
+use std::time::Instant;
+use std::{env, fs};
+
+use env_logger::Target;
+use log::LevelFilter;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::Terminal;
+
+use rts_core::components::displayer::Displayer;
+use rts_core::components::game_config::GameConfig;
+use rts_core::components::replay::Replay;
+use rts_core::components::turn_strategy::TurnStrategy;
+use rts_core::entity::player::Player;
+
+use crate::terminal::TerminalGuard;
+use crate::tui_displayer::{Overlay, TuiDisplayer};
+use crate::viewer::Viewer;
+
+const USAGE: &str = "Usage: rts-tui <replay.json> | rts-tui --live [config.json]";
+/// File the engine logs are appended to, the terminal belongs to the viewer
+const LOG_VARIABLE: &str = "RTS_TUI_LOG";
+
+/// Play a replay, or a live game between two AIs, in the terminal.
+/// Usage: rts-tui <replay.json> | rts-tui --live [config.json]
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args().nth(1).ok_or(USAGE)?;
+    if let Some(log_path) = env::var_os(LOG_VARIABLE) {
+        let log_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        env_logger::Builder::new()
+            .filter_level(LevelFilter::Debug)
+            .parse_default_env()
+            .target(Target::Pipe(Box::new(log_file)))
+            .init();
+    }
+
+    let mut viewer = if path == "--live" {
+        let config = match env::args().nth(2) {
+            Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+            None => GameConfig::default(),
+        };
+        let players = vec![
+            Player::new("AI 1".to_string(), TurnStrategy::build_order()),
+            Player::new("AI 2".to_string(), TurnStrategy::build_order()),
+        ];
+        Viewer::live(players, config)
+    } else {
+        let replay: Replay = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        Viewer::load(&replay)?
+    };
+
+    let _guard = TerminalGuard::enter()?;
+    let displayer = TuiDisplayer::new(Terminal::new(CrosstermBackend::new(stdout()))?);
+    run(&displayer, &mut viewer)
+}
+
+fn run(displayer: &TuiDisplayer, viewer: &mut Viewer) -> Result<(), Box<dyn Error>> {
+    let mut last_step = Instant::now();
+    loop {
+        displayer.set_overlay(overlay_of(viewer));
+        displayer.display(&viewer.current().snapshot)?;
+
+        let timeout = viewer.get_delay().saturating_sub(last_step.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char(' ') => viewer.toggle_pause(),
+                    KeyCode::Char('n') | KeyCode::Right => viewer.step()?,
+                    KeyCode::Char('b') | KeyCode::Left => viewer.rewind(),
+                    KeyCode::Char('+') => viewer.faster(),
+                    KeyCode::Char('-') => viewer.slower(),
+                    KeyCode::Tab => viewer.select_next(),
+                    KeyCode::BackTab => viewer.select_previous(),
+                    KeyCode::Esc => viewer.unselect(),
+                    _ => {}
+                }
+            }
+        }
+
+        if !viewer.is_paused() && last_step.elapsed() >= viewer.get_delay() {
+            viewer.step()?;
+            last_step = Instant::now();
+        }
+    }
+}
+
+fn overlay_of(viewer: &Viewer) -> Overlay {
+    let frame = viewer.current();
+    let state = if viewer.is_paused() {
+        "paused".to_string()
+    } else {
+        format!("playing x{}", viewer.get_speed())
+    };
+    let title = if viewer.is_live() {
+        format!("Turn {} (live) - {}", frame.snapshot.turn, state)
+    } else {
+        format!(
+            "Turn {}/{} - {}",
+            frame.snapshot.turn,
+            viewer.get_turn_count(),
+            state
+        )
+    };
+    Overlay {
+        title,
+        selected: viewer.get_selected(),
+        events: frame
+            .events
+            .iter()
+            .map(|notification| {
+                format!(
+                    "{} {:?}",
+                    notification.get_sequence(),
+                    notification.get_event()
+                )
+            })
+            .collect(),
+    }
+}
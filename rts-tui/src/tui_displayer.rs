@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::io::Stdout;
+
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Terminal;
+
+use rts_core::components::ascii_renderer::AsciiRenderer;
+use rts_core::components::displayer::Displayer;
+use rts_core::components::play_ground::{HasIdentifier, Identifier};
+use rts_core::components::snapshot::{Snapshot, UnitSnapshot};
+use rts_core::exceptions::RtsException;
+
+const PANEL_WIDTH: u16 = 44;
+const HELP: &str =
+    "space: pause | n/→: step | b/←: rewind | +/-: speed | tab: select | esc | q: quit";
+
+/// What the viewer shows around the map
+#[derive(Default)]
+pub struct Overlay {
+    pub title: String,
+    pub selected: Option<Identifier>,
+    pub events: Vec<String>,
+}
+
+/// Draw snapshots in the terminal, can be swapped with the console displayer
+pub struct TuiDisplayer {
+    terminal: RefCell<Terminal<CrosstermBackend<Stdout>>>,
+    overlay: RefCell<Overlay>,
+}
+
+impl TuiDisplayer {
+    pub fn new(terminal: Terminal<CrosstermBackend<Stdout>>) -> Self {
+        TuiDisplayer {
+            terminal: RefCell::new(terminal),
+            overlay: RefCell::new(Overlay::default()),
+        }
+    }
+
+    pub fn set_overlay(&self, overlay: Overlay) {
+        *self.overlay.borrow_mut() = overlay;
+    }
+
+    fn map_lines<'a>(snapshot: &Snapshot, selected: &Option<Identifier>) -> Vec<Line<'a>> {
+        let selected_tile = selected.and_then(|identifier| {
+            Self::find_unit(snapshot, &identifier)
+                .and_then(|placed| snapshot.map.tile_of(&placed.coordinate))
+        });
+        AsciiRenderer::grid(snapshot)
+            .into_iter()
+            .enumerate()
+            .map(|(y, row)| match selected_tile {
+                Some((x, tile_y)) if tile_y as usize == y => {
+                    let chars: Vec<char> = row.chars().collect();
+                    let start = x as usize * 2;
+                    Line::from(vec![
+                        Span::raw(chars[..start].iter().collect::<String>()),
+                        Span::styled(
+                            chars[start..start + 2].iter().collect::<String>(),
+                            Style::default().add_modifier(Modifier::REVERSED),
+                        ),
+                        Span::raw(chars[start + 2..].iter().collect::<String>()),
+                    ])
+                }
+                _ => Line::from(row),
+            })
+            .collect()
+    }
+
+    fn inspector_lines<'a>(snapshot: &Snapshot, selected: &Option<Identifier>) -> Vec<Line<'a>> {
+        let identifier = match selected {
+            Some(identifier) => identifier,
+            None => return vec![Line::from("Press tab to select a unit")],
+        };
+        let placed = match Self::find_unit(snapshot, identifier) {
+            Some(placed) => placed,
+            None => return vec![Line::from(format!("Unit {} is dead", identifier))],
+        };
+
+        let unit = &placed.unit;
+        let owner = match unit.get_owner() {
+            Some(_) => format!("player {}", AsciiRenderer::owner_symbol(unit.get_owner())),
+            None => "neutral".to_string(),
+        };
+        let mut lines = vec![
+            Line::from(format!(
+                "Unit {} ({}) of {}",
+                identifier,
+                unit.get_unit_type(),
+                owner
+            )),
+            Line::from(format!("at {}", placed.coordinate)),
+            Line::from(format!(
+                "health {} mana {} armor {} range {}",
+                unit.get_health(),
+                unit.get_mana(),
+                unit.get_armor(),
+                unit.get_range()
+            )),
+            Line::from(format!(
+                "rank {} experience {}",
+                unit.get_rank(),
+                unit.get_experience()
+            )),
+            Line::from(match unit.get_order() {
+                Some(order) => match order.get_path() {
+                    Some(path) => {
+                        match path.get_eta(&placed.coordinate, unit.get_effective_speed()) {
+                            Some(eta) => format!(
+                                "{:?} to {} in {} turns",
+                                order.get_kind(),
+                                path.get_destination(),
+                                eta
+                            ),
+                            None => format!("stuck on the way to {}", path.get_destination()),
+                        }
+                    }
+                    None => format!("{:?}", order.get_kind()),
+                },
+                None => "standing".to_string(),
+            }),
+            Line::from("Skills:"),
+        ];
+        lines.extend(unit.get_skills().iter().enumerate().map(|(i, skill)| {
+            Line::from(format!(
+                " #{} dmg {} mana {} lvl {} cd {}/{}ms",
+                i,
+                skill.get_damage(),
+                skill.get_mana_cost(),
+                skill.get_level(),
+                skill.get_actual_cooldown(),
+                skill.get_cooldown()
+            ))
+        }));
+        if !unit.get_effects().is_empty() {
+            lines.push(Line::from("Effects:"));
+            lines.extend(unit.get_effects().iter().map(|effect| {
+                Line::from(format!(" {:?} for {} turns", effect.kind, effect.turns))
+            }));
+        }
+        lines
+    }
+
+    fn find_unit<'a>(snapshot: &'a Snapshot, identifier: &Identifier) -> Option<&'a UnitSnapshot> {
+        snapshot
+            .units
+            .iter()
+            .find(|placed| placed.unit.is(identifier))
+    }
+}
+
+impl Displayer for TuiDisplayer {
+    fn display(&self, snapshot: &Snapshot) -> Result<(), RtsException> {
+        let overlay = self.overlay.borrow();
+        let mut terminal = self.terminal.borrow_mut();
+        terminal
+            .draw(|frame| {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(3),
+                        Constraint::Length(snapshot.players.len() as u16 + 2),
+                        Constraint::Length(1),
+                    ])
+                    .split(frame.area());
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(10), Constraint::Length(PANEL_WIDTH)])
+                    .split(rows[0]);
+                let panel = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                    .split(columns[1]);
+
+                let map = Paragraph::new(Self::map_lines(snapshot, &overlay.selected)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(overlay.title.clone()),
+                );
+                frame.render_widget(map, columns[0]);
+
+                let inspector = Paragraph::new(Self::inspector_lines(snapshot, &overlay.selected))
+                    .block(Block::default().borders(Borders::ALL).title("Unit"));
+                frame.render_widget(inspector, panel[0]);
+
+                let visible = panel[1].height.saturating_sub(2) as usize;
+                let skipped = overlay.events.len().saturating_sub(visible);
+                let events: Vec<Line> = overlay.events[skipped..]
+                    .iter()
+                    .map(|event| Line::from(event.clone()))
+                    .collect();
+                let events = Paragraph::new(events)
+                    .block(Block::default().borders(Borders::ALL).title("Events"));
+                frame.render_widget(events, panel[1]);
+
+                let players: Vec<Line> = snapshot
+                    .players
+                    .iter()
+                    .enumerate()
+                    .map(|(i, player)| Line::from(AsciiRenderer::status_line(i, player)))
+                    .collect();
+                let players = Paragraph::new(players)
+                    .block(Block::default().borders(Borders::ALL).title("Players"));
+                frame.render_widget(players, rows[1]);
+
+                frame.render_widget(Paragraph::new(HELP), rows[2]);
+            })
+            .map(|_| ())
+            .map_err(|e| RtsException::GeneralException(format!("Failed to draw: {}", e)))
+    }
+}
@@ -0,0 +1,269 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use rts_core::components::game::{Game, GameStateObserver};
+use rts_core::components::game_config::GameConfig;
+use rts_core::components::play_ground::{HasIdentifier, Identifier};
+use rts_core::components::replay::Replay;
+use rts_core::components::snapshot::Snapshot;
+use rts_core::components::turn_strategy::TurnStrategy;
+use rts_core::entity::game_event::Notification;
+use rts_core::entity::player::Player;
+use rts_core::exceptions::RtsException;
+
+/// Delay between two turns when the game is running, from slowest to fastest
+const SPEEDS_IN_MILLIS: [u64; 5] = [2000, 1000, 500, 250, 100];
+
+/// Keep the events of the game to show them next to the map
+pub struct EventFeed(Rc<RefCell<Vec<Notification>>>);
+
+impl GameStateObserver for EventFeed {
+    fn update(&self, notification: &Notification) {
+        self.0.borrow_mut().push(notification.clone());
+    }
+}
+
+/// State of a turn, as shown by the viewer
+pub struct Frame {
+    pub snapshot: Snapshot,
+    /// Events which led to this state
+    pub events: Vec<Notification>,
+}
+
+/// A game played turn by turn, as the viewer reaches its end
+struct LiveGame {
+    game: Game<EventFeed>,
+    feed: Rc<RefCell<Vec<Notification>>>,
+    is_over: bool,
+}
+
+impl LiveGame {
+    fn new(game: Game<EventFeed>, feed: Rc<RefCell<Vec<Notification>>>) -> Self {
+        LiveGame {
+            game,
+            feed,
+            is_over: false,
+        }
+    }
+
+    fn first_frame(&self) -> Frame {
+        Frame {
+            snapshot: self.game.snapshot(),
+            events: Vec::new(),
+        }
+    }
+
+    fn next_frame(&mut self) -> Result<Frame, RtsException> {
+        self.is_over = self.game.play_turn()?;
+        Ok(Frame {
+            snapshot: self.game.snapshot(),
+            events: self.feed.borrow_mut().drain(..).collect(),
+        })
+    }
+}
+
+/// Navigate in the turns of a game: play, pause, step and rewind
+pub struct Viewer {
+    frames: Vec<Frame>,
+    cursor: usize,
+    paused: bool,
+    speed: usize,
+    selected: Option<Identifier>,
+    /// None when watching a replay
+    live: Option<LiveGame>,
+}
+
+impl Viewer {
+    /// Play the whole replay with the engine, keeping every turn for rewinding
+    pub fn load(replay: &Replay) -> Result<Self, RtsException> {
+        let feed = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::from_replay(replay, vec![EventFeed(Rc::clone(&feed))]);
+        let mut replayed = LiveGame::new(game, feed);
+        let mut frames = vec![replayed.first_frame()];
+        for _ in 0..replay.turns.len() {
+            frames.push(replayed.next_frame()?);
+            if replayed.is_over {
+                break;
+            }
+        }
+        Ok(Self::with_frames(frames, None))
+    }
+
+    /// Start a new game, its turns are played when the viewer steps past the last one
+    pub fn live(players: Vec<Player<TurnStrategy>>, config: GameConfig) -> Self {
+        let feed = Rc::new(RefCell::new(Vec::new()));
+        let game = Game::with_config(players, vec![EventFeed(Rc::clone(&feed))], config);
+        let live = LiveGame::new(game, feed);
+        Self::with_frames(vec![live.first_frame()], Some(live))
+    }
+
+    fn with_frames(frames: Vec<Frame>, live: Option<LiveGame>) -> Self {
+        Viewer {
+            frames,
+            cursor: 0,
+            paused: true,
+            speed: 1,
+            selected: None,
+            live,
+        }
+    }
+
+    pub fn current(&self) -> &Frame {
+        &self.frames[self.cursor]
+    }
+
+    /// Turns known so far, a live game has more to come until it is over
+    pub fn get_turn_count(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused
+    }
+
+    /// Go to next turn, the viewer pauses on the last one.
+    /// A live game plays its next turn when the viewer is on the last one.
+    pub fn step(&mut self) -> Result<(), RtsException> {
+        if self.cursor + 1 == self.frames.len() {
+            if let Some(live) = self.live.as_mut().filter(|live| !live.is_over) {
+                let frame = live.next_frame()?;
+                self.frames.push(frame);
+            }
+        }
+        if self.cursor + 1 < self.frames.len() {
+            self.cursor += 1;
+        } else {
+            self.paused = true;
+        }
+        Ok(())
+    }
+
+    pub fn rewind(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS_IN_MILLIS.len() - 1)
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1)
+    }
+
+    pub fn get_delay(&self) -> Duration {
+        Duration::from_millis(SPEEDS_IN_MILLIS[self.speed])
+    }
+
+    pub fn get_speed(&self) -> usize {
+        self.speed + 1
+    }
+
+    /// Identifier of the inspected unit, it may be dead at the current turn
+    pub fn get_selected(&self) -> Option<Identifier> {
+        self.selected
+    }
+
+    /// Select next unit of the current turn, by identifier order
+    pub fn select_next(&mut self) {
+        let units = &self.current().snapshot.units;
+        let position = self.selected_position();
+        self.selected = match position {
+            Some(i) => units.get((i + 1) % units.len()),
+            None => units.first(),
+        }
+        .map(|placed| placed.unit.get_identifier());
+    }
+
+    pub fn select_previous(&mut self) {
+        let units = &self.current().snapshot.units;
+        let position = self.selected_position();
+        self.selected = match position {
+            Some(0) | None => units.last(),
+            Some(i) => units.get(i - 1),
+        }
+        .map(|placed| placed.unit.get_identifier());
+    }
+
+    pub fn unselect(&mut self) {
+        self.selected = None
+    }
+
+    fn selected_position(&self) -> Option<usize> {
+        let selected = self.selected?;
+        self.current()
+            .snapshot
+            .units
+            .iter()
+            .position(|placed| placed.unit.is(&selected))
+    }
+}
+
+#[cfg(test)]
+mod test_viewer {
+
+    use rts_core::components::game_config::GameConfig;
+    use rts_core::components::replay::{Replay, ReplayPlayer};
+    use rts_core::components::turn_strategy::TurnStrategy;
+    use rts_core::entity::game_actions::Action;
+    use rts_core::entity::player::Player;
+    use rts_core::entity::unit::UnitType;
+
+    use super::Viewer;
+
+    #[test]
+    pub fn should_step_and_rewind_turns() {
+        let mut replay = Replay::new(
+            GameConfig::default(),
+            vec![ReplayPlayer {
+                name: "Tigran".to_string(),
+                money: 0,
+            }],
+        );
+        replay.turns.push(vec![Action::BuyUnit(UnitType::Classic)]);
+        replay.turns.push(vec![Action::EndGame]);
+        let mut viewer = Viewer::load(&replay).unwrap();
+
+        assert_eq!(2, viewer.get_turn_count());
+        viewer.step().unwrap();
+        viewer.select_next();
+        assert_eq!(Some(1), viewer.get_selected());
+        viewer.step().unwrap();
+        viewer.step().unwrap();
+        assert_eq!(2, viewer.current().snapshot.turn);
+        viewer.rewind();
+        viewer.rewind();
+        assert_eq!(0, viewer.current().snapshot.turn);
+        assert!(viewer.current().snapshot.units.is_empty());
+    }
+
+    #[test]
+    pub fn should_play_live_games_while_stepping() {
+        let players = vec![
+            Player::new("Tigran".to_string(), TurnStrategy::build_order()),
+            Player::new("Emma".to_string(), TurnStrategy::build_order()),
+        ];
+        let mut viewer = Viewer::live(players, GameConfig::default());
+        assert!(viewer.is_live());
+        assert_eq!(0, viewer.get_turn_count());
+
+        viewer.step().unwrap();
+        viewer.step().unwrap();
+        assert_eq!(2, viewer.get_turn_count());
+        assert_eq!(2, viewer.current().snapshot.turn);
+        assert!(!viewer.current().events.is_empty());
+
+        // Turns already played are shown again, not played twice
+        viewer.rewind();
+        viewer.step().unwrap();
+        assert_eq!(2, viewer.get_turn_count());
+    }
+}